@@ -0,0 +1,251 @@
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::{Config, Parameter, ValueSchema};
+
+#[derive(Debug, Clone)]
+/// A compiled `pattern`, so requests don't pay for compiling it
+pub struct CompiledPattern(Regex);
+
+impl PartialEq for CompiledPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Config {
+    /// Compiles the `pattern` of every parameter; invalid patterns are reported by validation, so they are skipped here
+    pub(crate) fn compile_patterns(&mut self) {
+        for endpoint in &mut self.endpoints {
+            let params = [
+                &mut endpoint.path_arguments,
+                &mut endpoint.query_params,
+                &mut endpoint.body_params,
+            ];
+            for param in params.into_iter().flatten().flatten() {
+                param.compile_pattern();
+            }
+        }
+    }
+}
+
+impl Parameter {
+    pub(crate) fn compile_pattern(&mut self) {
+        self.compiled_pattern = self
+            .pattern
+            .as_deref()
+            .and_then(|pattern| Regex::new(pattern).ok())
+            .map(CompiledPattern);
+    }
+
+    /// Checks a parsed value against the parameter's constraints, and the nested schemas of its items and properties, returning why it was rejected
+    pub fn check_constraints(&self, value: &Value) -> Result<(), String> {
+        if let Some(allowed) = &self.enum_values {
+            if !allowed.iter().any(|a| same_value(a, value)) {
+                let allowed: Vec<String> = allowed.iter().map(display_value).collect();
+                return Err(format!("must be one of {}", allowed.join(", ")));
+            }
+        }
+
+        if let Some(number) = value.as_f64() {
+            if let Some(minimum) = self.minimum.filter(|m| number < *m) {
+                return Err(format!("must be at least {}", minimum));
+            }
+            if let Some(maximum) = self.maximum.filter(|m| number > *m) {
+                return Err(format!("must be at most {}", maximum));
+            }
+        }
+
+        if let Some(string) = value.as_str() {
+            let length = string.chars().count();
+            if let Some(min_length) = self.min_length.filter(|m| length < *m) {
+                return Err(format!("must be at least {} characters long", min_length));
+            }
+            if let Some(max_length) = self.max_length.filter(|m| length > *m) {
+                return Err(format!("must be at most {} characters long", max_length));
+            }
+            if let Some(pattern) = &self.pattern {
+                // Patterns are compiled when the configuration is loaded, so only parameters built some other way are compiled here
+                let matches = match &self.compiled_pattern {
+                    Some(CompiledPattern(regex)) => regex.is_match(string),
+                    None => Regex::new(pattern).is_ok_and(|regex| regex.is_match(string)),
+                };
+                if !matches {
+                    return Err(format!("must match the pattern `{}`", pattern));
+                }
+            }
+        }
+
+        if let Some(items) = value.as_array() {
+            if let Some(min_items) = self.min_items.filter(|m| items.len() < *m) {
+                return Err(format!("must have at least {} items", min_items));
+            }
+            if let Some(max_items) = self.max_items.filter(|m| items.len() > *m) {
+                return Err(format!("must have at most {} items", max_items));
+            }
+        }
+
+        check_nested(
+            self.items.as_deref(),
+            self.properties.as_ref(),
+            value,
+            &self.from,
+        )
+    }
+}
+
+impl ValueSchema {
+    /// Checks that a value within an `object` or `array` parameter has the expected kind and shape
+    fn check(&self, value: &Value, path: &str) -> Result<(), String> {
+        if !self.kind.matches(value) {
+            return Err(format!("`{}` must be of kind `{}`", path, self.kind));
+        }
+        check_nested(self.items.as_deref(), self.properties.as_ref(), value, path)
+    }
+}
+
+/// Checks the items of an array and the fields of an object against their schemas; `path` names the value in errors, e.g. `input.tags[1]`
+fn check_nested(
+    items: Option<&ValueSchema>,
+    properties: Option<&BTreeMap<String, ValueSchema>>,
+    value: &Value,
+    path: &str,
+) -> Result<(), String> {
+    if let (Some(schema), Some(values)) = (items, value.as_array()) {
+        for (index, item) in values.iter().enumerate() {
+            schema.check(item, &format!("{}[{}]", path, index))?;
+        }
+    }
+    if let (Some(properties), Some(object)) = (properties, value.as_object()) {
+        for (name, schema) in properties {
+            let field_path = format!("{}.{}", path, name);
+            match object.get(name) {
+                None | Some(Value::Null) if schema.required => {
+                    return Err(format!("the required field `{}` is missing", field_path));
+                }
+                None | Some(Value::Null) => {}
+                Some(field) => schema.check(field, &field_path)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compares values, treating numbers as equal if they have the same value so `1` matches `1.0`
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => a == b,
+    }
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ParamKind;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_constraints() {
+        let order = Parameter {
+            from: "order".to_string(),
+            enum_values: Some(vec![json!("ASC"), json!("DESC")]),
+            ..Default::default()
+        };
+        assert_eq!(order.check_constraints(&json!("ASC")), Ok(()));
+        assert_eq!(
+            order.check_constraints(&json!("UP")),
+            Err("must be one of ASC, DESC".to_string())
+        );
+
+        let limit = Parameter {
+            from: "limit".to_string(),
+            kind: ParamKind::INT,
+            minimum: Some(1.0),
+            maximum: Some(100.0),
+            ..Default::default()
+        };
+        assert_eq!(limit.check_constraints(&json!(100)), Ok(()));
+        assert_eq!(
+            limit.check_constraints(&json!(0)),
+            Err("must be at least 1".to_string())
+        );
+        assert_eq!(
+            limit.check_constraints(&json!(101)),
+            Err("must be at most 100".to_string())
+        );
+
+        let mut slug = Parameter {
+            from: "slug".to_string(),
+            pattern: Some("^[a-z-]+$".to_string()),
+            max_length: Some(8),
+            ..Default::default()
+        };
+        slug.compile_pattern();
+        assert!(slug.compiled_pattern.is_some());
+        assert_eq!(slug.check_constraints(&json!("my-post")), Ok(()));
+        assert_eq!(
+            slug.check_constraints(&json!("My-Post")),
+            Err("must match the pattern `^[a-z-]+$`".to_string())
+        );
+        assert_eq!(
+            slug.check_constraints(&json!("a-very-long-post")),
+            Err("must be at most 8 characters long".to_string())
+        );
+
+        let ids = Parameter {
+            from: "ids".to_string(),
+            kind: ParamKind::ARRAY,
+            min_items: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids.check_constraints(&json!([])),
+            Err("must have at least 1 items".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check_nested_schemas() {
+        let schema = |kind: ParamKind, required: bool| ValueSchema {
+            kind,
+            required,
+            ..Default::default()
+        };
+        let input = Parameter {
+            from: "input".to_string(),
+            kind: ParamKind::OBJECT,
+            properties: Some(BTreeMap::from([
+                ("email".to_string(), schema(ParamKind::STRING, true)),
+                (
+                    "tags".to_string(),
+                    ValueSchema {
+                        items: Some(Box::new(schema(ParamKind::STRING, false))),
+                        ..schema(ParamKind::ARRAY, false)
+                    },
+                ),
+            ])),
+            ..Default::default()
+        };
+        assert_eq!(
+            input.check_constraints(&json!({"email": "a@b.c", "tags": ["x"], "other": 1})),
+            Ok(())
+        );
+        assert_eq!(
+            input.check_constraints(&json!({"email": null})),
+            Err("the required field `input.email` is missing".to_string())
+        );
+        assert_eq!(
+            input.check_constraints(&json!({"email": "a@b.c", "tags": ["x", 2]})),
+            Err("`input.tags[1]` must be of kind `string`".to_string())
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use super::ValidationError;
+
+/// Expands `${VAR}` and `${VAR:-default}` references in every string value of a configuration file using the process environment.
+/// Every undefined variable is reported rather than stopping at the first one.
+pub(crate) fn interpolate_config<T: Serialize + DeserializeOwned>(
+    config: T,
+) -> Result<T, Vec<ValidationError>> {
+    interpolate_config_with(config, |name| std::env::var(name).ok())
+}
+
+fn interpolate_config_with<T: Serialize + DeserializeOwned>(
+    config: T,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<T, Vec<ValidationError>> {
+    // Round-tripping through JSON lets us walk every string without listing each field; only strings change, so the result still deserializes
+    let mut value = serde_json::to_value(&config).expect("config is always serializable");
+    let mut errors = Vec::new();
+    interpolate_value(&mut value, String::new(), &lookup, &mut errors);
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(serde_json::from_value(value).expect("interpolation only changes string values"))
+}
+
+fn interpolate_value(
+    value: &mut Value,
+    key_path: String,
+    lookup: &impl Fn(&str) -> Option<String>,
+    errors: &mut Vec<ValidationError>,
+) {
+    match value {
+        Value::String(s) => match interpolate(s, lookup) {
+            Ok(expanded) => *s = expanded,
+            Err(message) => errors.push(ValidationError {
+                file: None,
+                key_path,
+                message,
+            }),
+        },
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                interpolate_value(item, format!("{}[{}]", key_path, index), lookup, errors);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let child_path = if key_path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", key_path, key)
+                };
+                interpolate_value(item, child_path, lookup, errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Expands the variable references in a single string; `$${` can be used to write a literal `${`
+fn interpolate(input: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(escaped) = rest.strip_prefix("$${") {
+            output.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let Some(reference) = rest.strip_prefix("${") else {
+            output.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+        let Some(end) = reference.find('}') else {
+            return Err(format!("unterminated variable reference in `{}`", input));
+        };
+
+        let (name, default) = match reference[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&reference[..end], None),
+        };
+        // Like the shell, `:-` falls back to the default for empty variables as well as unset ones
+        match (
+            lookup(name).filter(|v| !v.is_empty() || default.is_none()),
+            default,
+        ) {
+            (Some(value), _) => output.push_str(&value),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => {
+                return Err(format!(
+                    "environment variable `{}` is not set and has no default",
+                    name
+                ))
+            }
+        }
+        rest = &reference[end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOST" => Some("graphql.internal".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(
+            interpolate("https://${HOST}/graphql", &lookup),
+            Ok("https://graphql.internal/graphql".to_string())
+        );
+        assert_eq!(
+            interpolate("${PORT:-4000}", &lookup),
+            Ok("4000".to_string())
+        );
+        assert_eq!(
+            interpolate("${EMPTY:-fallback}", &lookup),
+            Ok("fallback".to_string())
+        );
+        assert_eq!(interpolate("${EMPTY}", &lookup), Ok(String::new()));
+        assert_eq!(
+            interpolate("$5 and $${HOST}", &lookup),
+            Ok("$5 and ${HOST}".to_string())
+        );
+        assert_eq!(
+            interpolate("${MISSING}", &lookup),
+            Err("environment variable `MISSING` is not set and has no default".to_string())
+        );
+        assert!(interpolate("${HOST", &lookup).is_err());
+    }
+
+    #[test]
+    fn test_interpolate_config_reports_key_paths() {
+        let yaml = r#"
+common:
+    listen: "0.0.0.0:${PORT:-8080}"
+    graphql_endpoint: "https://${HOST}/"
+endpoints:
+    - path: "/users"
+      pq_id: "${USERS_PQ_ID}"
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+
+        let interpolated = interpolate_config_with(config.clone(), |name| match name {
+            "USERS_PQ_ID" => Some("abc".to_string()),
+            _ => lookup(name),
+        })
+        .unwrap();
+        assert_eq!(interpolated.common.listen, "0.0.0.0:8080");
+        assert_eq!(
+            interpolated.common.graphql_endpoint,
+            "https://graphql.internal/"
+        );
+        assert_eq!(interpolated.endpoints[0].pq_id, "abc");
+
+        let errors = interpolate_config_with(config, lookup).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![ValidationError {
+                file: None,
+                key_path: "endpoints[0].pq_id".to_string(),
+                message: "environment variable `USERS_PQ_ID` is not set and has no default"
+                    .to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use serde_json::{json, Value};
+use std::fmt;
+use std::path::PathBuf;
+
+use super::ValidationError;
+
+#[derive(Debug)]
+/// The errors that can occur while loading the configuration file
+pub enum ConfigError {
+    /// The configuration file could not be read from disk
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The configuration file, or a file it references, is not valid or does not match the expected structure
+    Parse {
+        path: PathBuf,
+        /// Where in the file the error was found, if known
+        location: Option<Location>,
+        /// The location of the offending key; e.g. `endpoints[3].query_params[0].kind`
+        key_path: Option<String>,
+        message: String,
+        /// A possible fix for the error, such as the closest valid value for an unknown variant
+        suggestion: Option<String>,
+    },
+    /// The included files or the configuration directory could not be resolved
+    Include { path: PathBuf, message: String },
+    /// The configuration file was parsed but failed semantic validation
+    Invalid {
+        path: PathBuf,
+        errors: Vec<ValidationError>,
+    },
+    /// The configuration passed validation, but the router refused its routes
+    Routes { path: PathBuf, message: String },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// A 1-based line and column within a file
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ConfigError {
+    pub(crate) fn from_yaml(
+        path: PathBuf,
+        error: serde_path_to_error::Error<serde_yaml::Error>,
+    ) -> Self {
+        let key_path = error.path().iter().next().map(|_| error.path().to_string());
+        let inner = error.into_inner();
+        let location = inner.location().map(|l| Location {
+            line: l.line(),
+            column: l.column(),
+        });
+
+        Self::parse(path, key_path, inner.to_string(), location)
+    }
+
+    pub(crate) fn from_json(
+        path: PathBuf,
+        error: serde_path_to_error::Error<serde_json::Error>,
+    ) -> Self {
+        let key_path = error.path().iter().next().map(|_| error.path().to_string());
+        let inner = error.into_inner();
+        let location = Some(Location {
+            line: inner.line(),
+            column: inner.column(),
+        })
+        .filter(|l| l.line > 0);
+
+        Self::parse(path, key_path, inner.to_string(), location)
+    }
+
+    fn parse(
+        path: PathBuf,
+        key_path: Option<String>,
+        mut message: String,
+        location: Option<Location>,
+    ) -> Self {
+        // serde_yaml and serde_json bake the key path and location into the message, so strip them back out
+        if let Some(ref key_path) = key_path {
+            if let Some(stripped) = message.strip_prefix(&format!("{}: ", key_path)) {
+                message = stripped.to_string();
+            }
+        }
+        if let Some(ref location) = location {
+            let suffix = format!(" at line {} column {}", location.line, location.column);
+            message = message.replacen(&suffix, "", 1);
+        }
+
+        let suggestion = suggest(&message);
+
+        ConfigError::Parse {
+            path,
+            location,
+            key_path,
+            message,
+            suggestion,
+        }
+    }
+
+    /// The process exit code to use when the configuration can't be loaded; these follow the BSD `sysexits.h` conventions
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            // EX_NOINPUT
+            ConfigError::Read { .. } => 66,
+            // EX_DATAERR
+            ConfigError::Parse { .. }
+            | ConfigError::Include { .. }
+            | ConfigError::Invalid { .. }
+            | ConfigError::Routes { .. } => 65,
+        }
+    }
+
+    /// A machine-readable version of the error, listing every problem with its location
+    pub fn to_json(&self) -> Value {
+        match self {
+            ConfigError::Read { path, source } => json!({
+                "file": path,
+                "errors": [{ "message": source.to_string() }],
+            }),
+            ConfigError::Parse {
+                path,
+                location,
+                key_path,
+                message,
+                suggestion,
+            } => json!({
+                "file": path,
+                "errors": [{
+                    "key_path": key_path,
+                    "line": location.map(|l| l.line),
+                    "column": location.map(|l| l.column),
+                    "message": message,
+                    "suggestion": suggestion,
+                }],
+            }),
+            ConfigError::Include { path, message } | ConfigError::Routes { path, message } => {
+                json!({
+                    "file": path,
+                    "errors": [{ "message": message }],
+                })
+            }
+            ConfigError::Invalid { path, errors } => json!({
+                "file": path,
+                "errors": errors,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(f, "Failed to read {}: {}", path.display(), source)
+            }
+            ConfigError::Parse {
+                path,
+                location,
+                key_path,
+                message,
+                suggestion,
+            } => {
+                write!(f, "Failed to parse {}", path.display())?;
+                if let Some(location) = location {
+                    write!(f, " at line {}, column {}", location.line, location.column)?;
+                }
+                match key_path {
+                    Some(key_path) => write!(f, "\n  in `{}`: {}", key_path, message)?,
+                    None => write!(f, "\n  {}", message)?,
+                }
+                if let Some(suggestion) = suggestion {
+                    write!(f, "\n  help: {}", suggestion)?;
+                }
+                Ok(())
+            }
+            ConfigError::Include { path, message } => {
+                write!(f, "Failed to load config {}: {}", path.display(), message)
+            }
+            ConfigError::Routes { path, message } => {
+                write!(
+                    f,
+                    "Unable to register the routes of {}: {}",
+                    path.display(),
+                    message
+                )
+            }
+            ConfigError::Invalid { path, errors } => {
+                write!(
+                    f,
+                    "Invalid config file {}; found {} error(s)",
+                    path.display(),
+                    errors.len()
+                )?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read { source, .. } => Some(source),
+            ConfigError::Parse { .. }
+            | ConfigError::Include { .. }
+            | ConfigError::Invalid { .. }
+            | ConfigError::Routes { .. } => None,
+        }
+    }
+}
+
+/// Looks for an `unknown variant` or `unknown field` message and suggests the closest expected value
+fn suggest(message: &str) -> Option<String> {
+    let rest = message
+        .strip_prefix("unknown variant ")
+        .or_else(|| message.strip_prefix("unknown field "))?;
+    let mut quoted = rest.split('`').skip(1).step_by(2);
+    let given = quoted.next()?.to_lowercase();
+
+    let expected: Vec<&str> = quoted.collect();
+    let closest = expected
+        .iter()
+        .filter_map(|candidate| {
+            let lower = candidate.to_lowercase();
+            let distance = if lower == given {
+                0
+            } else if given.starts_with(&lower) || lower.starts_with(&given) {
+                1
+            } else {
+                levenshtein(&given, &lower)
+            };
+            if distance <= std::cmp::max(2, given.len() / 3) {
+                Some((distance, *candidate))
+            } else {
+                None
+            }
+        })
+        .min_by_key(|(distance, _)| *distance)?;
+
+    Some(format!("did you mean `{}`?", closest.1))
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config_str;
+
+    #[test]
+    fn test_parse_error_reports_location_and_key_path() {
+        let yaml = r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+endpoints:
+    - path: "/users"
+      pq_id: "123"
+      query_params:
+          - from: "sort"
+            kind: integer
+"#;
+        let error = parse_config_str(PathBuf::from("config.yaml"), yaml).unwrap_err();
+        match error {
+            ConfigError::Parse {
+                location,
+                key_path,
+                suggestion,
+                ..
+            } => {
+                assert_eq!(
+                    location,
+                    Some(Location {
+                        line: 9,
+                        column: 19
+                    })
+                );
+                assert_eq!(
+                    key_path.as_deref(),
+                    Some("endpoints[0].query_params[0].kind")
+                );
+                assert_eq!(suggestion.as_deref(), Some("did you mean `int`?"));
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_invalid_config_to_json() {
+        let error = ConfigError::Invalid {
+            path: PathBuf::from("config.yaml"),
+            errors: vec![ValidationError {
+                file: None,
+                key_path: "endpoints[0].pq_id".to_string(),
+                message: "not a hash".to_string(),
+            }],
+        };
+        assert_eq!(error.exit_code(), 65);
+        assert_eq!(
+            error.to_json(),
+            json!({
+                "file": "config.yaml",
+                "errors": [{"key_path": "endpoints[0].pq_id", "message": "not a hash"}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_suggest() {
+        assert_eq!(
+            suggest("unknown variant `get`, expected one of `GET`, `POST`, `PUT`"),
+            Some("did you mean `GET`?".to_string())
+        );
+        assert_eq!(
+            suggest("unknown variant `strng`, expected one of `int`, `string`, `float`"),
+            Some("did you mean `string`?".to_string())
+        );
+        assert_eq!(
+            suggest("unknown variant `nothing`, expected `int` or `string`"),
+            None
+        );
+        assert_eq!(suggest("missing field `path`"), None);
+    }
+}
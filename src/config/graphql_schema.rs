@@ -0,0 +1,194 @@
+use apollo_compiler::{ExecutableDocument, Schema};
+use std::path::Path;
+
+use super::{read_file, variable_path, Config, ConfigError, ValidationError};
+use crate::operation::operation_variables;
+
+/// Loads the SDL referenced by `common.schema_file`, relative to the main configuration file
+pub(crate) fn load_schema(config: &mut Config, main_file: &Path) -> Result<(), ConfigError> {
+    let Some(schema_file) = &config.common.schema_file else {
+        return Ok(());
+    };
+    let path = main_file
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(schema_file);
+    let sdl = read_file(&path)?;
+
+    match Schema::parse_and_validate(sdl, &path) {
+        Ok(schema) => {
+            config.schema = Some(schema);
+            config.files.push(path);
+            Ok(())
+        }
+        Err(e) => Err(ConfigError::Parse {
+            path,
+            location: None,
+            key_path: None,
+            message: e.errors.to_string(),
+            suggestion: None,
+        }),
+    }
+}
+
+impl Config {
+    /// Checks each endpoint's operation against the schema, and that every parameter maps onto a variable of the operation with a compatible type.
+    /// This only applies to endpoints whose operation is in the persisted query manifest.
+    pub(crate) fn check_operations(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let (Some(schema), Some(manifest)) = (&self.schema, &self.manifest) else {
+            return errors;
+        };
+
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            let Some(operation) = manifest.operation(&endpoint.pq_id) else {
+                continue;
+            };
+            let (file, key_path) = endpoint.location(index);
+
+            if let Err(e) = ExecutableDocument::parse_and_validate(
+                schema,
+                operation.body.as_str(),
+                "operation.graphql",
+            ) {
+                errors.push(ValidationError {
+                    file,
+                    key_path,
+                    message: format!(
+                        "the operation `{}` is not valid against the schema: {}",
+                        operation.name, e.errors
+                    ),
+                });
+                continue;
+            }
+            // The operation was just validated, so it must parse
+            let Ok(variables) = operation_variables(&operation.body, Some(&operation.name)) else {
+                continue;
+            };
+
+            let expected = variables
+                .iter()
+                .map(|v| format!("`{}`", v.name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let sources = [
+                ("path_arguments", &endpoint.path_arguments),
+                ("query_params", &endpoint.query_params),
+                ("body_params", &endpoint.body_params),
+            ];
+            for (source, params) in sources {
+                for (param_index, param) in params.iter().flatten().enumerate() {
+                    let path = param.variable_path();
+                    let name = &path[0];
+                    let message = match variables.iter().find(|v| &v.name == name) {
+                        None => format!(
+                            "`{}` is not a variable of the operation `{}`; expected one of {}",
+                            name, operation.name, expected
+                        ),
+                        // The kind of a field within an input object isn't checked
+                        Some(variable)
+                            if path.len() == 1 && !variable.accepts(param.kind, schema) =>
+                        {
+                            format!(
+                                "`kind: {}` can't be used for the variable `${}: {}`",
+                                param.kind, variable.name, variable.ty
+                            )
+                        }
+                        Some(_) => continue,
+                    };
+                    errors.push(ValidationError {
+                        file: file.clone(),
+                        key_path: format!("{}.{}[{}]", key_path, source, param_index),
+                        message,
+                    });
+                }
+            }
+
+            // The variables set by the body as a whole must also exist; the keys of a spread body are only known for its `allow` list
+            let body = endpoint.body.iter().flat_map(|body| {
+                let to = body
+                    .to
+                    .iter()
+                    .map(|to| (variable_path(to).swap_remove(0), "body.to".to_string()));
+                let allow = body
+                    .allow
+                    .iter()
+                    .flatten()
+                    .enumerate()
+                    .map(|(index, key)| (key.clone(), format!("body.allow[{}]", index)));
+                to.chain(allow).collect::<Vec<_>>()
+            });
+            for (name, body_path) in body {
+                if !variables.iter().any(|v| v.name == name) {
+                    errors.push(ValidationError {
+                        file: file.clone(),
+                        key_path: format!("{}.{}", key_path, body_path),
+                        message: format!(
+                            "`{}` is not a variable of the operation `{}`; expected one of {}",
+                            name, operation.name, expected
+                        ),
+                    });
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::testing::{TestDir, HASH};
+    use crate::config::{parse_config, ConfigError};
+
+    #[test]
+    fn test_check_operations() {
+        let dir = TestDir::new(
+            "schema",
+            &[
+                (
+                    "schema.graphql",
+                    "type Query { users(limit: Int!, sort: UserSort): [User] }\ntype User { id: ID! }\nenum UserSort { NAME, CREATED }".to_string(),
+                ),
+                (
+                    "manifest.json",
+                    serde_json::json!({
+                        "operations": [{
+                            "id": HASH,
+                            "name": "GetUsers",
+                            "type": "query",
+                            "body": "query GetUsers($limit: Int!, $sort: UserSort) { users(limit: $limit, sort: $sort) { id } }"
+                        }]
+                    })
+                    .to_string(),
+                ),
+                (
+                    "config.yaml",
+                    "common:\n  graphql_endpoint: http://localhost/\n  persisted_query_manifest: manifest.json\n  schema_file: schema.graphql\nendpoints:\n  - path: /users\n    operation_name: GetUsers\n    query_params:\n      - from: limit\n        kind: string\n      - from: order\n        to: sortt\n".to_string(),
+                ),
+            ],
+        );
+        let config_path = dir.join("config.yaml");
+
+        let errors = match parse_config(config_path.to_str().unwrap()) {
+            Err(ConfigError::Invalid { errors, .. }) => errors,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let messages: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.key_path.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "endpoints[0].query_params[0]",
+                    "`kind: string` can't be used for the variable `$limit: Int!`"
+                ),
+                (
+                    "endpoints[0].query_params[1]",
+                    "`sortt` is not a variable of the operation `GetUsers`; expected one of `limit`, `sort`"
+                ),
+            ]
+        );
+    }
+}
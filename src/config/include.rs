@@ -0,0 +1,206 @@
+use std::path::{Path, PathBuf};
+
+use super::{parse_file, read_file, Config, ConfigError, Endpoint, EndpointSource, EndpointsFile};
+
+/// Loads every `.yaml` and `.yml` file in a directory; exactly one of them must contain the `common` section and the rest are treated as included endpoint files
+/// Returns the merged configuration along with the path of the main file
+pub(crate) fn load_directory(dir: &Path) -> Result<(Config, PathBuf), ConfigError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(source) => {
+            return Err(ConfigError::Read {
+                path: dir.to_path_buf(),
+                source,
+            })
+        }
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_file()
+                && matches!(
+                    path.extension().and_then(|e| e.to_str()),
+                    Some("yaml") | Some("yml")
+                )
+        })
+        .collect();
+    files.sort();
+
+    let mut main: Option<(PathBuf, String)> = None;
+    let mut others = Vec::new();
+    for file in files {
+        let contents = read_file(&file)?;
+        // Syntax errors are reported with their location once the file is parsed properly below
+        let has_common = serde_yaml::from_str::<serde_yaml::Value>(&contents)
+            .map(|v| v.get("common").is_some())
+            .unwrap_or(false);
+        if !has_common {
+            others.push((file, contents));
+            continue;
+        }
+        if let Some((existing, _)) = &main {
+            return Err(ConfigError::Include {
+                path: dir.to_path_buf(),
+                message: format!(
+                    "both {} and {} contain a `common` section; only one file may",
+                    existing.display(),
+                    file.display()
+                ),
+            });
+        }
+        main = Some((file, contents));
+    }
+
+    let Some((main_file, main_contents)) = main else {
+        return Err(ConfigError::Include {
+            path: dir.to_path_buf(),
+            message: "no file contains a `common` section".to_string(),
+        });
+    };
+
+    let mut config: Config = parse_file(&main_file, &main_contents)?;
+    tag_sources(&mut config.endpoints, &main_file);
+    config.files = vec![dir.to_path_buf(), main_file.clone()];
+
+    for (file, contents) in others {
+        let included: EndpointsFile = parse_file(&file, &contents)?;
+        add_endpoints(&mut config, file, included.endpoints);
+    }
+
+    resolve_patterns(&mut config, &main_file)?;
+    Ok((config, main_file))
+}
+
+/// Adds the endpoints from every file listed in the configuration's `include` section
+pub(crate) fn resolve_includes(config: &mut Config, path: &Path) -> Result<(), ConfigError> {
+    tag_sources(&mut config.endpoints, path);
+    config.files = vec![path.to_path_buf()];
+    resolve_patterns(config, path)
+}
+
+fn resolve_patterns(config: &mut Config, path: &Path) -> Result<(), ConfigError> {
+    let base = path.parent().unwrap_or(Path::new(""));
+    for pattern in config.include.clone().unwrap_or_default() {
+        for file in expand_pattern(path, base, &pattern)? {
+            // Skip files that are already part of the configuration, such as the main file matching its own glob
+            if config.files.iter().any(|f| same_file(f, &file)) {
+                continue;
+            }
+            let contents = read_file(&file)?;
+            let included: EndpointsFile = parse_file(&file, &contents)?;
+            add_endpoints(config, file, included.endpoints);
+        }
+    }
+    Ok(())
+}
+
+fn expand_pattern(path: &Path, base: &Path, pattern: &str) -> Result<Vec<PathBuf>, ConfigError> {
+    let joined = base.join(pattern);
+    if !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![joined]);
+    }
+
+    let invalid = |message: String| ConfigError::Include {
+        path: path.to_path_buf(),
+        message,
+    };
+    let paths = match glob::glob(&joined.to_string_lossy()) {
+        Ok(paths) => paths,
+        Err(e) => return Err(invalid(format!("invalid include `{}`: {}", pattern, e))),
+    };
+    let mut files = Vec::new();
+    for entry in paths {
+        match entry {
+            Ok(file) if file.is_file() => files.push(file),
+            Ok(_) => {}
+            Err(e) => return Err(invalid(format!("unable to read `{}`: {}", pattern, e))),
+        }
+    }
+    Ok(files)
+}
+
+fn add_endpoints(config: &mut Config, file: PathBuf, mut endpoints: Vec<Endpoint>) {
+    tag_sources(&mut endpoints, &file);
+    config.endpoints.extend(endpoints);
+    config.files.push(file);
+}
+
+fn tag_sources(endpoints: &mut [Endpoint], file: &Path) {
+    for (index, endpoint) in endpoints.iter_mut().enumerate() {
+        endpoint.source = Some(EndpointSource {
+            file: file.to_path_buf(),
+            index,
+        });
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::testing::{TestDir, HASH};
+    use crate::config::{parse_config, ConfigError};
+
+    #[test]
+    fn test_include_globs() {
+        let dir = TestDir::new(
+            "include",
+            &[
+                (
+                    "config.yaml",
+                    format!(
+                        "common:\n  graphql_endpoint: http://localhost/\ninclude:\n  - endpoints/*.yaml\nendpoints:\n  - path: /a\n    pq_id: {HASH}\n"
+                    ),
+                ),
+                (
+                    "endpoints/users.yaml",
+                    format!("endpoints:\n  - path: /users\n    pq_id: {HASH}\n"),
+                ),
+                (
+                    "endpoints/products.yaml",
+                    format!("endpoints:\n  - path: /products\n    pq_id: {HASH}\n"),
+                ),
+            ],
+        );
+
+        let config = parse_config(dir.join("config.yaml").to_str().unwrap()).unwrap();
+        let paths: Vec<&str> = config.endpoints.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["/a", "/products", "/users"]);
+        assert_eq!(config.files.len(), 3);
+    }
+
+    #[test]
+    fn test_directory_reports_duplicates_with_file() {
+        let dir = TestDir::new(
+            "directory",
+            &[
+                (
+                    "main.yaml",
+                    "common:\n  graphql_endpoint: http://localhost/\n".to_string(),
+                ),
+                (
+                    "a.yaml",
+                    format!("endpoints:\n  - path: /users\n    pq_id: {HASH}\n"),
+                ),
+                (
+                    "b.yml",
+                    format!("endpoints:\n  - path: /other\n    pq_id: {HASH}\n  - path: /users\n    pq_id: {HASH}\n"),
+                ),
+            ],
+        );
+
+        let errors = match parse_config(dir.to_str().unwrap()) {
+            Err(ConfigError::Invalid { errors, .. }) => errors,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].file, Some(dir.join("b.yml")));
+        assert_eq!(errors[0].key_path, "endpoints[1]");
+        assert!(errors[0].message.contains("a.yaml"));
+    }
+}
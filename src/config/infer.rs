@@ -0,0 +1,188 @@
+use apollo_compiler::Schema;
+
+use super::{variable_path, Config, Endpoint, HttpMethod, ParamKind, Parameter, ValidationError};
+use crate::operation::{operation_variables, OperationVariable};
+
+impl Config {
+    /// Adds a parameter for every variable of an endpoint's operation that isn't already mapped by an explicit parameter.
+    /// This only applies to endpoints whose operation is in the persisted query manifest, since the operation body is needed.
+    pub(crate) fn infer_parameters(&mut self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let Some(manifest) = &self.manifest else {
+            return errors;
+        };
+        let schema = self.schema.as_ref().map(|s| s.as_ref());
+
+        for (index, endpoint) in self.endpoints.iter_mut().enumerate() {
+            let Some(operation) = manifest.operation(&endpoint.pq_id) else {
+                continue;
+            };
+            match operation_variables(&operation.body, Some(&operation.name)) {
+                Ok(variables) => {
+                    for variable in variables {
+                        infer_parameter(endpoint, variable, schema);
+                    }
+                }
+                Err(e) => {
+                    let (file, key_path) = endpoint.location(index);
+                    errors.push(ValidationError {
+                        file,
+                        key_path,
+                        message: format!(
+                            "unable to parse the operation `{}` from the persisted query manifest: {}",
+                            operation.name, e
+                        ),
+                    });
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// Maps a variable onto the endpoint unless a parameter already targets it.
+/// Variables named after a `:name` or `*name` path segment become path arguments; the rest become query parameters for `GET` and `DELETE` endpoints and body parameters otherwise.
+fn infer_parameter(endpoint: &mut Endpoint, variable: OperationVariable, schema: Option<&Schema>) {
+    let explicit = [
+        &endpoint.path_arguments,
+        &endpoint.query_params,
+        &endpoint.body_params,
+    ]
+    .into_iter()
+    .flatten()
+    .flatten()
+    .any(|p| p.variable() == variable.name)
+        || endpoint
+            .body
+            .as_ref()
+            .and_then(|body| body.to.as_deref())
+            .is_some_and(|to| variable_path(to)[0] == variable.name);
+    if explicit {
+        return;
+    }
+
+    let in_path = endpoint
+        .path
+        .split('/')
+        .any(|segment| segment.strip_prefix([':', '*']) == Some(variable.name.as_str()));
+    let in_query = !in_path && matches!(endpoint.method, HttpMethod::GET | HttpMethod::DELETE);
+    let kind = match variable.kind(schema) {
+        Some(kind) => kind,
+        // Path and query values are text, which suits enums and custom scalars.
+        // A body value of an unknown type may be an input object, which would be sent as a string, so it must be mapped explicitly
+        None if !in_path && !in_query => return,
+        None => ParamKind::STRING,
+    };
+    let params = if in_path {
+        &mut endpoint.path_arguments
+    } else if in_query {
+        &mut endpoint.query_params
+    } else if endpoint.body.is_some() {
+        // The body is already passed as a whole, either to another variable or spread over all of them
+        return;
+    } else {
+        &mut endpoint.body_params
+    };
+
+    params.get_or_insert_with(Vec::new).push(Parameter {
+        from: variable.name.clone(),
+        to: None,
+        required: variable.required(),
+        kind,
+        ..Default::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::testing::{TestDir, HASH};
+    use crate::config::{parse_config, ParamKind, Parameter};
+
+    #[test]
+    fn test_infer_parameters() {
+        let dir = TestDir::new(
+            "infer",
+            &[
+                (
+                    "manifest.json",
+                    serde_json::json!({
+                        "operations": [
+                            {
+                                "id": HASH,
+                                "name": "GetUserPosts",
+                                "type": "query",
+                                "body": "query GetUserPosts($id: ID!, $limit: Int = 10, $tags: [String!], $sort: String!) { user(id: $id) { posts(limit: $limit, tags: $tags, sort: $sort) { id } } }"
+                            },
+                            {
+                                "id": "1f2a5c3b4d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708",
+                                "name": "UpdateUser",
+                                "type": "mutation",
+                                "body": "mutation UpdateUser($id: ID!, $input: UserInput!, $notify: Boolean) { updateUser(id: $id, input: $input, notify: $notify) { id } }"
+                            }
+                        ]
+                    })
+                    .to_string(),
+                ),
+                (
+                    "config.yaml",
+                    "common:\n  graphql_endpoint: http://localhost/\n  persisted_query_manifest: manifest.json\nendpoints:\n  - path: /users/:id/posts\n    operation_name: GetUserPosts\n    query_params:\n      - from: order\n        to: sort\n        required: false\n  - path: /accounts/*id\n    method: PATCH\n    operation_name: UpdateUser\n".to_string(),
+                ),
+            ],
+        );
+        let config_path = dir.join("config.yaml");
+
+        let config = parse_config(config_path.to_str().unwrap()).unwrap();
+        let endpoint = &config.endpoints[0];
+        assert_eq!(
+            endpoint.path_arguments,
+            Some(vec![Parameter {
+                from: "id".to_string(),
+                to: None,
+                required: true,
+                kind: ParamKind::STRING,
+                ..Default::default()
+            }])
+        );
+        assert_eq!(
+            endpoint.query_params,
+            Some(vec![
+                Parameter {
+                    from: "order".to_string(),
+                    to: Some("sort".to_string()),
+                    required: false,
+                    kind: ParamKind::STRING,
+                    ..Default::default()
+                },
+                Parameter {
+                    from: "limit".to_string(),
+                    to: None,
+                    required: false,
+                    kind: ParamKind::INT,
+                    ..Default::default()
+                },
+                Parameter {
+                    from: "tags".to_string(),
+                    to: None,
+                    required: false,
+                    kind: ParamKind::ARRAY,
+                    ..Default::default()
+                },
+            ])
+        );
+        assert_eq!(endpoint.body_params, None);
+
+        // Without a schema, `$input: UserInput!` may be an input object, so it isn't inferred as a body parameter
+        let endpoint = &config.endpoints[1];
+        assert_eq!(endpoint.path_arguments.as_ref().unwrap()[0].from, "id");
+        assert_eq!(
+            endpoint.body_params,
+            Some(vec![Parameter {
+                from: "notify".to_string(),
+                to: None,
+                required: false,
+                kind: ParamKind::BOOLEAN,
+                ..Default::default()
+            }])
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use super::{read_file, Config, ConfigError, ValidationError};
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+/// An Apollo persisted query manifest, as generated by `@apollo/generate-persisted-query-manifest` or the Rover CLI
+pub struct Manifest {
+    pub operations: Vec<ManifestOperation>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct ManifestOperation {
+    /// The persisted query ID; this is the SHA-256 hash of the operation body
+    pub id: String,
+    /// The name of the operation
+    pub name: String,
+    #[serde(rename = "type")]
+    /// The type of operation; e.g. `query` or `mutation`
+    pub operation_type: String,
+    /// The full GraphQL document for the operation
+    pub body: String,
+}
+
+impl Manifest {
+    /// Finds the operation with the given persisted query ID
+    pub fn operation(&self, id: &str) -> Option<&ManifestOperation> {
+        self.operations.iter().find(|o| o.id == id)
+    }
+}
+
+/// Loads the manifest referenced by `common.persisted_query_manifest`, relative to the main configuration file
+pub(crate) fn load_manifest(config: &mut Config, main_file: &Path) -> Result<(), ConfigError> {
+    let Some(manifest_path) = &config.common.persisted_query_manifest else {
+        return Ok(());
+    };
+    let path = main_file
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(manifest_path);
+    let contents = read_file(&path)?;
+
+    let deserializer = &mut serde_json::Deserializer::from_str(&contents);
+    match serde_path_to_error::deserialize(deserializer) {
+        Ok(manifest) => {
+            config.manifest = Some(manifest);
+            config.files.push(path);
+            Ok(())
+        }
+        Err(e) => Err(ConfigError::from_json(path, e)),
+    }
+}
+
+impl Config {
+    /// Fills in the `pq_id` of every endpoint that references its operation by `operation_name`
+    pub(crate) fn resolve_operations(&mut self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        for (index, endpoint) in self.endpoints.iter_mut().enumerate() {
+            let Some(name) = &endpoint.operation_name else {
+                continue;
+            };
+            let (file, key_path) = endpoint.location(index);
+            let mut error = |message: String| {
+                errors.push(ValidationError {
+                    file: file.clone(),
+                    key_path: format!("{}.operation_name", key_path),
+                    message,
+                })
+            };
+
+            let Some(manifest) = &self.manifest else {
+                error(
+                    "`operation_name` requires `common.persisted_query_manifest` to be set"
+                        .to_string(),
+                );
+                continue;
+            };
+            let mut ids: Vec<&str> = manifest
+                .operations
+                .iter()
+                .filter(|o| &o.name == name)
+                .map(|o| o.id.as_str())
+                .collect();
+            ids.sort();
+            ids.dedup();
+
+            match ids.as_slice() {
+                [] => error(format!(
+                    "the operation `{}` is not in the persisted query manifest",
+                    name
+                )),
+                [id] if endpoint.pq_id.is_empty() => endpoint.pq_id = id.to_string(),
+                [id] if endpoint.pq_id == *id => {}
+                [id] => error(format!(
+                    "`pq_id` is `{}` but the operation `{}` has the ID `{}` in the persisted query manifest",
+                    endpoint.pq_id, name, id
+                )),
+                ids => error(format!(
+                    "the operation name `{}` is ambiguous; it matches the IDs {} in the persisted query manifest",
+                    name,
+                    ids.join(", ")
+                )),
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::testing::{TestDir, HASH as USER_HASH};
+    use crate::config::{parse_config, ConfigError};
+
+    const OTHER_HASH: &str = "1f2a5c3b4d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708";
+
+    #[test]
+    fn test_resolve_operations() {
+        let dir = TestDir::new(
+            "manifest",
+            &[(
+                "manifest.json",
+                serde_json::json!({
+                "format": "apollo-persisted-query-manifest",
+                "version": 1,
+                "operations": [
+                    {"id": USER_HASH, "name": "GetUser", "type": "query", "body": "query GetUser { user { id } }"},
+                    {"id": USER_HASH, "name": "Duplicate", "type": "query", "body": "query Duplicate { a }"},
+                    {"id": OTHER_HASH, "name": "Duplicate", "type": "query", "body": "query Duplicate { b }"},
+                ]
+            })
+                .to_string(),
+            )],
+        );
+
+        let config_path = dir.join("config.yaml");
+        dir.write(
+            "config.yaml",
+            "common:\n  graphql_endpoint: http://localhost/\n  persisted_query_manifest: manifest.json\nendpoints:\n  - path: /user\n    operation_name: GetUser\n",
+        );
+        let config = parse_config(config_path.to_str().unwrap()).unwrap();
+        assert_eq!(config.endpoints[0].pq_id, USER_HASH);
+
+        dir.write(
+            "config.yaml",
+            "common:\n  graphql_endpoint: http://localhost/\n  persisted_query_manifest: manifest.json\nendpoints:\n  - path: /a\n    operation_name: Missing\n  - path: /b\n    operation_name: Duplicate\n",
+        );
+        let errors = match parse_config(config_path.to_str().unwrap()) {
+            Err(ConfigError::Invalid { errors, .. }) => errors,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "the operation `Missing` is not in the persisted query manifest".to_string(),
+                format!("the operation name `Duplicate` is ambiguous; it matches the IDs {}, {} in the persisted query manifest", OTHER_HASH, USER_HASH),
+            ]
+        );
+    }
+}
@@ -1,6 +1,25 @@
+use apollo_compiler::{validation::Valid, Schema};
 use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
 use serde_json::{Number, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+mod constraints;
+mod env;
+mod error;
+mod graphql_schema;
+mod include;
+mod infer;
+mod manifest;
+#[cfg(test)]
+pub(crate) mod testing;
+mod validate;
+
+pub use constraints::CompiledPattern;
+pub use error::ConfigError;
+pub use manifest::{Manifest, ManifestOperation};
+pub use validate::ValidationError;
 
 // TODO: Review the use of pub(crate) and pub
 // TODO: Review the serde attributes
@@ -42,6 +61,26 @@ pub enum ParamKind {
 pub struct Config {
     /// The common configuration for the server
     pub common: ServerConfig,
+    /// Other files whose endpoints should be added to this configuration; paths and globs are relative to this file
+    pub include: Option<Vec<String>>,
+    #[serde(default)]
+    /// The list of endpoints that the server should expose
+    pub endpoints: Vec<Endpoint>,
+    #[serde(skip)]
+    /// Every file that was read to build this configuration
+    pub files: Vec<PathBuf>,
+    #[serde(skip)]
+    /// The persisted query manifest referenced by `common.persisted_query_manifest`, if any
+    pub manifest: Option<Manifest>,
+    #[serde(skip)]
+    /// The GraphQL schema referenced by `common.schema_file`, if any
+    pub schema: Option<Valid<Schema>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
+/// A file of endpoints that is included from the main configuration
+pub struct EndpointsFile {
+    #[serde(default)]
     /// The list of endpoints that the server should expose
     pub endpoints: Vec<Endpoint>,
 }
@@ -58,30 +97,212 @@ pub struct ServerConfig {
     pub graphql_endpoint: String,
     /// Basic logging configuration
     pub logging: Option<Logging>,
+    /// The path to an Apollo persisted query manifest, relative to this file; this allows endpoints to use `operation_name` instead of `pq_id`
+    pub persisted_query_manifest: Option<String>,
+    /// The path to the GraphQL schema SDL, relative to this file; when set along with `persisted_query_manifest`, each endpoint's parameters are checked against its operation's variables
+    pub schema_file: Option<String>,
+    /// Settings for the generated OpenAPI document
+    pub openapi: Option<OpenApiConfig>,
+    /// Serves a documentation page for the endpoints when set
+    pub docs: Option<DocsConfig>,
+    /// Rules for the status code of responses with GraphQL errors; these apply after the endpoint's own rules
+    pub status_mapping: Option<Vec<StatusMapping>>,
+    /// Which parts of the GraphQL errors are removed before they are returned; by default errors are returned as-is
+    pub redact_errors: Option<ErrorRedaction>,
+    #[serde(default)]
+    /// The format of error responses; defaults to `graphql`
+    pub error_format: ErrorFormat,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+/// How the values of an `array` or `object` query parameter are written, following OpenAPI's `style`
+pub enum ParamStyle {
+    /// Repeated keys such as `?ids=1&ids=2` when exploded, or comma-separated values such as `?ids=1,2` when not
+    Form,
+    /// Space-separated values such as `?ids=1%202`
+    SpaceDelimited,
+    /// Pipe-separated values such as `?ids=1|2`
+    PipeDelimited,
+    /// Bracketed keys for the fields of an `object`, such as `?filter[status]=ACTIVE&filter[owner][id]=5`
+    DeepObject,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+/// The format of error responses
+pub enum ErrorFormat {
+    #[default]
+    /// A GraphQL response; e.g. `{"errors": [{"message": "..."}], "data": null}`
+    Graphql,
+    /// An RFC 9457 `application/problem+json` response
+    ProblemJson,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ErrorRedaction {
+    #[serde(default)]
+    /// Whether to remove the `locations` of each error
+    pub locations: bool,
+    #[serde(default)]
+    /// Whether to remove the `path` of each error
+    pub path: bool,
+    #[serde(default)]
+    /// Dotted paths within `extensions` to remove; e.g. `exception.stacktrace`
+    pub extensions: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct StatusMapping {
+    /// Matches errors whose `extensions.code` is this value; e.g. `NOT_FOUND`
+    pub code: Option<String>,
+    /// Matches errors whose `path` starts with this dotted path; e.g. `user.posts`, where `*` matches any segment
+    pub path: Option<String>,
+    /// The HTTP status code to respond with
+    pub status: u16,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct OpenApiConfig {
+    #[serde(default = "default_openapi_title")]
+    /// The title of the API
+    pub title: String,
+    #[serde(default = "default_openapi_version")]
+    /// The version of the API; defaults to `1.0.0`
+    pub version: String,
+    /// A description of the API
+    pub description: Option<String>,
+    /// If set, the OpenAPI document is served on this path under the `path_prefix`; e.g. `/openapi.json`
+    pub path: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct DocsConfig {
+    #[serde(default = "default_docs_path")]
+    /// The path the documentation page is served on, outside of the `path_prefix`; defaults to `/docs`
+    pub path: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct Endpoint {
     /// The path that the endpoint should be exposed on
     pub path: String,
     #[serde(default = "default_endpoint_method")]
     /// The method that the endpoint should accept
     pub method: HttpMethod,
-    /// The persisted query ID that the endpoint should use
+    #[serde(default)]
+    /// The persisted query ID that the endpoint should use; this is the SHA-256 hash of the operation. Either this or `operation_name` is required
     pub pq_id: String,
+    /// The name of the operation in the persisted query manifest; its ID is used as the `pq_id`
+    pub operation_name: Option<String>,
     /// The query parameters that the endpoint should accept
     pub query_params: Option<Vec<Parameter>>,
     /// The path arguments that the endpoint should accept
     pub path_arguments: Option<Vec<Parameter>>,
     /// The body parameters that the endpoint should accept
     pub body_params: Option<Vec<Parameter>>,
+    /// Passes the request body to the operation as a whole, as an alternative to listing `body_params`
+    pub body: Option<BodyConfig>,
+    /// A short summary of the endpoint for the OpenAPI document
+    pub summary: Option<String>,
+    /// A longer description of the endpoint for the OpenAPI document
+    pub description: Option<String>,
+    /// Tags for grouping the endpoint in the OpenAPI document
+    pub tags: Option<Vec<String>>,
+    /// The `operationId` in the OpenAPI document; defaults to the method and path, e.g. `get_users_id`
+    pub operation_id: Option<String>,
+    /// How the GraphQL response is turned into the endpoint's response body; by default the whole response is returned
+    pub response: Option<ResponseConfig>,
+    /// Rules for the status code of responses with GraphQL errors; these take precedence over `common.status_mapping`
+    pub status_mapping: Option<Vec<StatusMapping>>,
+    /// Which parts of the GraphQL errors are removed before they are returned; this replaces `common.redact_errors`
+    pub redact_errors: Option<ErrorRedaction>,
+    /// The format of error responses; this overrides `common.error_format`
+    pub error_format: Option<ErrorFormat>,
+    #[serde(skip)]
+    /// Where the endpoint was defined, for error reporting
+    pub source: Option<EndpointSource>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ResponseConfig {
+    /// A JSON Pointer into the GraphQL response for the value to return instead of the whole response; e.g. `/data/user`
+    pub select: Option<String>,
+    #[serde(default)]
+    /// Whether to respond with a 404 when the selected value is null or missing and there are no errors
+    pub not_found_when_null: bool,
+    #[serde(default)]
+    /// What to do with the GraphQL errors when `select` is set; by default they are kept
+    pub errors: ResponseErrors,
+    #[serde(default)]
+    /// Transforms applied in order to the `data` of the GraphQL response, before `select`
+    pub transforms: Vec<Transform>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// A single step of the response transform pipeline
+pub enum Transform {
+    /// Renames every field to the given case
+    RenameCase { case: FieldCase },
+    /// Removes the named fields wherever they appear; e.g. `__typename`
+    DropFields { fields: Vec<String> },
+    /// Replaces every connection, an object with `edges: [{node}]`, with the list of its nodes
+    FlattenConnections,
+    /// Adds fields with static values to an object
+    AddFields {
+        #[serde(default)]
+        /// A JSON Pointer into `data` for the object to add the fields to; defaults to `data` itself
+        at: String,
+        /// The fields to add and their values
+        fields: serde_json::Map<String, Value>,
+    },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+/// The case that field names are renamed to
+pub enum FieldCase {
+    SnakeCase,
+    CamelCase,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, Default, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+/// Whether the GraphQL errors are kept in the response body
+pub enum ResponseErrors {
+    #[default]
+    /// If there are errors, the body is `{"data": <selected value>, "errors": [...]}`
+    KEEP,
+    /// The body is always the selected value; errors are only reflected in the status code
+    DROP,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// The file an endpoint was defined in and its position in that file's `endpoints` list
+pub struct EndpointSource {
+    pub file: PathBuf,
+    pub index: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default, JsonSchema)]
+/// How the whole request body is passed to the operation; either `to` or `spread` must be set
+pub struct BodyConfig {
+    /// The variable the entire JSON body is bound to; like a parameter's `to`, this can be a path to a field within an input object variable
+    pub to: Option<String>,
+    #[serde(default)]
+    /// Whether each top-level key of a JSON object body is passed as a variable of the same name
+    pub spread: bool,
+    /// When spreading, the only keys that are passed; other keys are ignored
+    pub allow: Option<Vec<String>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema, Default)]
 pub struct Parameter {
     /// The parameter name that the user will use; e.g. `id` in `/user/:id` or /user/?id=1234
     pub from: String,
-    /// If the operation uses a different name, this is the name the variable should be renamed to
+    /// If the operation uses a different name, this is the name the variable should be renamed to.
+    /// A dotted path such as `input.filter.status`, or a JSON Pointer such as `/input/filter/status`, sets a field within an input object variable
     pub to: Option<String>,
     #[serde(default = "default_parameter_required")]
     /// Whether the parameter is required or not; by default it is false
@@ -89,6 +310,54 @@ pub struct Parameter {
     #[serde(default = "default_parameter_kind")]
     /// The kind of parameter that is expected if it is not a string
     pub kind: ParamKind,
+    /// The value of the variable when an optional parameter is missing from the request; it must be of the parameter's `kind`
+    pub default: Option<Value>,
+    #[serde(default)]
+    /// Whether to set the variable to `null` when an optional parameter without a `default` is missing, rather than leaving it out
+    pub null_on_missing: bool,
+    /// How the values of an `array` or `object` query parameter are written; by default the value is parsed as JSON, e.g. `?ids=[1,2]`
+    pub style: Option<ParamStyle>,
+    /// Whether each value of a styled `array` parameter is a separate key, e.g. `?ids=1&ids=2`; defaults to true for `form` and `deep_object`, and false otherwise
+    pub explode: Option<bool>,
+    /// The expected shape of each item of an `array` parameter
+    pub items: Option<Box<ValueSchema>>,
+    /// The expected shape of the fields of an `object` parameter, by name
+    pub properties: Option<BTreeMap<String, ValueSchema>>,
+    #[serde(rename = "enum")]
+    /// The only values that are accepted, e.g. `[ASC, DESC]`
+    pub enum_values: Option<Vec<Value>>,
+    /// The smallest value accepted for an `int` or `float` parameter
+    pub minimum: Option<f64>,
+    /// The largest value accepted for an `int` or `float` parameter
+    pub maximum: Option<f64>,
+    /// A regular expression that a `string` parameter must match somewhere in its value; use `^` and `$` to match the whole value
+    pub pattern: Option<String>,
+    #[serde(skip)]
+    /// The `pattern`, compiled once when the configuration is loaded
+    pub compiled_pattern: Option<CompiledPattern>,
+    /// The fewest characters accepted for a `string` parameter
+    pub min_length: Option<usize>,
+    /// The most characters accepted for a `string` parameter
+    pub max_length: Option<usize>,
+    /// The fewest items accepted for an `array` parameter
+    pub min_items: Option<usize>,
+    /// The most items accepted for an `array` parameter
+    pub max_items: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema, Default)]
+/// The expected shape of a value within an `array` or `object` parameter
+pub struct ValueSchema {
+    #[serde(default = "default_parameter_kind")]
+    /// The kind of the value
+    pub kind: ParamKind,
+    #[serde(default)]
+    /// Whether the field must be present, and not null, within its object; by default it is false
+    pub required: bool,
+    /// The expected shape of each item of an `array` value
+    pub items: Option<Box<ValueSchema>>,
+    /// The expected shape of the fields of an `object` value, by name
+    pub properties: Option<BTreeMap<String, ValueSchema>>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
@@ -101,6 +370,29 @@ pub struct Logging {
     pub level: LogLevel,
 }
 
+impl Default for HttpMethod {
+    fn default() -> Self {
+        default_endpoint_method()
+    }
+}
+
+impl Default for ParamKind {
+    fn default() -> Self {
+        default_parameter_kind()
+    }
+}
+
+impl Default for OpenApiConfig {
+    fn default() -> Self {
+        OpenApiConfig {
+            title: default_openapi_title(),
+            version: default_openapi_version(),
+            description: None,
+            path: None,
+        }
+    }
+}
+
 fn default_endpoint_method() -> HttpMethod {
     HttpMethod::GET
 }
@@ -116,6 +408,15 @@ fn default_server_path_prefix() -> String {
 fn default_server_listen() -> String {
     "127.0.0.1:4000".to_string()
 }
+fn default_openapi_title() -> String {
+    "persisted-query-to-rest".to_string()
+}
+fn default_openapi_version() -> String {
+    "1.0.0".to_string()
+}
+fn default_docs_path() -> String {
+    "/docs".to_string()
+}
 fn default_logging_format() -> String {
     "pretty".to_string()
 }
@@ -123,15 +424,150 @@ fn default_logging_level() -> LogLevel {
     LogLevel::INFO
 }
 
-// TODO: improve error handling
-pub fn parse_config(path: &str) -> Config {
-    let yaml_contents = std::fs::read_to_string(path).expect("Failed to read config file");
-    let config: Config = serde_yaml::from_str(&yaml_contents).expect("Failed to parse config file");
+/// Loads the configuration from a file, or from every YAML file in a directory, along with any included files, and validates it
+pub fn parse_config(path: &str) -> Result<Config, ConfigError> {
+    let path = PathBuf::from(path);
+    let (mut config, main_file) = if path.is_dir() {
+        include::load_directory(&path)?
+    } else {
+        let yaml_contents = read_file(&path)?;
+        let mut config: Config = parse_file(&path, &yaml_contents)?;
+        include::resolve_includes(&mut config, &path)?;
+        (config, path.clone())
+    };
+    manifest::load_manifest(&mut config, &main_file)?;
+    graphql_schema::load_schema(&mut config, &main_file)?;
 
-    config
+    let mut errors = config.resolve_operations();
+    errors.extend(config.infer_parameters());
+    errors.extend(config.check_operations());
+    if let Err(e) = config.validate() {
+        errors.extend(e);
+    }
+
+    if errors.is_empty() {
+        config.compile_patterns();
+        Ok(config)
+    } else {
+        Err(ConfigError::Invalid { path, errors })
+    }
+}
+
+#[cfg(test)]
+/// Parses and validates a single configuration file without resolving its includes
+pub(crate) fn parse_config_str(path: PathBuf, yaml_contents: &str) -> Result<Config, ConfigError> {
+    let mut config: Config = parse_file(&path, yaml_contents)?;
+    match config.validate() {
+        Ok(()) => {
+            config.compile_patterns();
+            Ok(config)
+        }
+        Err(errors) => Err(ConfigError::Invalid { path, errors }),
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(contents),
+        Err(source) => Err(ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Deserializes a single file and expands its environment variables
+fn parse_file<T: serde::de::DeserializeOwned + Serialize>(
+    path: &Path,
+    yaml_contents: &str,
+) -> Result<T, ConfigError> {
+    let deserializer = serde_yaml::Deserializer::from_str(yaml_contents);
+    let parsed = match serde_path_to_error::deserialize(deserializer) {
+        Ok(parsed) => parsed,
+        Err(e) => return Err(ConfigError::from_yaml(path.to_path_buf(), e)),
+    };
+    match env::interpolate_config(parsed) {
+        Ok(parsed) => Ok(parsed),
+        Err(errors) => Err(ConfigError::Invalid {
+            path: path.to_path_buf(),
+            errors,
+        }),
+    }
+}
+
+impl std::fmt::Display for ParamKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ParamKind::INT => "int",
+            ParamKind::STRING => "string",
+            ParamKind::FLOAT => "float",
+            ParamKind::OBJECT => "object",
+            ParamKind::ARRAY => "array",
+            ParamKind::BOOLEAN => "boolean",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Splits a variable target, either a dotted path such as `input.filter.status` or a JSON Pointer such as `/input/filter/status`, into its segments
+pub fn variable_path(target: &str) -> Vec<String> {
+    match target.strip_prefix('/') {
+        Some(pointer) => pointer
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+        None => target.split('.').map(str::to_string).collect(),
+    }
+}
+
+impl std::fmt::Display for ParamStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ParamStyle::Form => "form",
+            ParamStyle::SpaceDelimited => "space_delimited",
+            ParamStyle::PipeDelimited => "pipe_delimited",
+            ParamStyle::DeepObject => "deep_object",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Parameter {
+    /// The path of the value this parameter sets, starting with the variable's name; e.g. `["input", "filter", "status"]` for `to: input.filter.status`
+    pub fn variable_path(&self) -> Vec<String> {
+        match &self.to {
+            Some(to) => variable_path(to),
+            None => vec![self.from.clone()],
+        }
+    }
+
+    /// Whether each value of a styled parameter is a separate key, following OpenAPI's defaults
+    pub fn explode(&self) -> bool {
+        self.explode.unwrap_or(matches!(
+            self.style,
+            Some(ParamStyle::Form | ParamStyle::DeepObject)
+        ))
+    }
+
+    /// The name of the operation variable this parameter sets
+    pub fn variable(&self) -> String {
+        self.variable_path().swap_remove(0)
+    }
 }
 
 impl ParamKind {
+    /// Whether a JSON value, such as one from the configuration or within an `object` or `array` parameter, is of this kind
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            ParamKind::INT => value.is_i64() || value.is_u64(),
+            ParamKind::STRING => value.is_string(),
+            ParamKind::FLOAT => value.is_number(),
+            ParamKind::OBJECT => value.is_object(),
+            ParamKind::ARRAY => value.is_array(),
+            ParamKind::BOOLEAN => value.is_boolean(),
+        }
+    }
+
     pub fn from_str(&self, param: &str) -> Result<Value, Box<dyn std::error::Error>> {
         match self {
             ParamKind::INT => match param.parse::<i64>() {
@@ -140,7 +576,11 @@ impl ParamKind {
             },
             ParamKind::STRING => Ok(Value::String(param.to_string())),
             ParamKind::FLOAT => match param.parse::<f64>() {
-                Ok(f) => Ok(Value::Number(Number::from_f64(f).unwrap())),
+                // JSON has no representation for `NaN` or infinite values
+                Ok(f) => match Number::from_f64(f) {
+                    Some(n) => Ok(Value::Number(n)),
+                    None => Err(Box::from("the number must be finite")),
+                },
                 Err(e) => Err(Box::from(e.to_string().as_str())),
             },
             ParamKind::OBJECT | ParamKind::ARRAY => match serde_json::from_str(param) {
@@ -0,0 +1,49 @@
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// The persisted query ID used by the configuration tests
+pub const HASH: &str = "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38";
+
+/// A temporary directory of test files, which is removed when it's dropped
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// Creates an empty directory unique to the test `name` and writes `files` into it, creating their parent directories
+    pub fn new(name: &str, files: &[(&str, String)]) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "persisted-query-to-rest-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        let dir = TestDir { path };
+        for (file, contents) in files {
+            dir.write(file, contents);
+        }
+        dir
+    }
+
+    /// Writes a file within the directory, replacing it if it exists
+    pub fn write(&self, file: &str, contents: &str) {
+        let path = self.path.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,1100 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use super::{
+    variable_path, Config, Endpoint, HttpMethod, ParamKind, ParamStyle, Parameter, StatusMapping,
+    Transform, ValueSchema,
+};
+use crate::openapi::operation_id;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+/// A single semantic problem found in an otherwise well-formed configuration
+pub struct ValidationError {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The file the problem was found in, if it isn't the main configuration file
+    pub file: Option<PathBuf>,
+    /// The location of the offending key; e.g. `endpoints[2].path_arguments[0]`
+    pub key_path: String,
+    /// A description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(
+                f,
+                "in `{}` of {}: {}",
+                self.key_path,
+                file.display(),
+                self.message
+            ),
+            None => write!(f, "in `{}`: {}", self.key_path, self.message),
+        }
+    }
+}
+
+impl Config {
+    /// Checks the configuration for problems that would otherwise only show up at runtime, such as duplicate routes or path arguments missing from the path.
+    /// All problems are collected rather than stopping at the first one.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        // The router matches paths regardless of the method, so only an identical path can be reused by another method
+        let mut routes = Vec::<(HttpMethod, &str, String)>::new();
+        // Generated IDs are included, since they must be unique in the OpenAPI document as well
+        let mut operation_ids = HashMap::<String, String>::new();
+
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            let (file, key_path) = endpoint.location(index);
+            validate_endpoint(endpoint, &file, &key_path, &mut errors);
+
+            let conflict = routes.iter().find_map(|(other_method, other_path, other)| {
+                if *other_path == endpoint.path {
+                    (*other_method == endpoint.method).then_some((other_path, other, None))
+                } else {
+                    route_conflict(&endpoint.path, other_path)
+                        .map(|reason| (other_path, other, Some(reason)))
+                }
+            });
+            let route_conflicts = conflict.is_some();
+            if let Some((other_path, other, reason)) = conflict {
+                if let Some(reason) = reason {
+                    errors.push(ValidationError {
+                        file: file.clone(),
+                        key_path: format!("{}.path", key_path),
+                        message: format!(
+                            "`{}` overlaps with `{}` from {}; {}",
+                            endpoint.path, other_path, other, reason
+                        ),
+                    });
+                } else {
+                    errors.push(ValidationError {
+                        file: file.clone(),
+                        key_path: key_path.clone(),
+                        message: format!(
+                            "{:?} {} is already defined by {}",
+                            endpoint.method, endpoint.path, other
+                        ),
+                    });
+                }
+            }
+            let description = match &file {
+                Some(file) => format!("{} in {}", key_path, file.display()),
+                None => key_path.clone(),
+            };
+
+            let operation_id = operation_id(endpoint);
+            match operation_ids.get(&operation_id) {
+                Some(other) if endpoint.operation_id.is_some() => errors.push(ValidationError {
+                    file: file.clone(),
+                    key_path: format!("{}.operation_id", key_path),
+                    message: format!(
+                        "the operation ID `{}` is already used by {}",
+                        operation_id, other
+                    ),
+                }),
+                // A route that is already reported as a duplicate would otherwise be reported twice
+                Some(_) if route_conflicts => {}
+                Some(other) => errors.push(ValidationError {
+                    file: file.clone(),
+                    key_path: key_path.clone(),
+                    message: format!(
+                        "the generated operation ID `{}` is already used by {}; set `operation_id` to make it unique",
+                        operation_id, other
+                    ),
+                }),
+                None => {
+                    operation_ids.insert(operation_id, description.clone());
+                }
+            }
+            routes.push((endpoint.method, &endpoint.path, description));
+        }
+
+        if let Some(path) = self.common.openapi.as_ref().and_then(|o| o.path.as_ref()) {
+            // The document is served with `GET`, so it can share a path with endpoints of other methods
+            let conflict = routes.iter().find_map(|(method, other_path, other)| {
+                if *other_path == path {
+                    (*method == HttpMethod::GET).then(|| {
+                        format!(
+                            "`{}` overlaps with GET `{}` from {}",
+                            path, other_path, other
+                        )
+                    })
+                } else {
+                    route_conflict(path, other_path).map(|reason| {
+                        format!(
+                            "`{}` overlaps with `{}` from {}; {}",
+                            path, other_path, other, reason
+                        )
+                    })
+                }
+            });
+            if !path.starts_with('/') {
+                errors.push(ValidationError {
+                    file: None,
+                    key_path: "common.openapi.path".to_string(),
+                    message: format!("`{}` must start with a `/`", path),
+                });
+            } else if let Some(message) = conflict {
+                errors.push(ValidationError {
+                    file: None,
+                    key_path: "common.openapi.path".to_string(),
+                    message,
+                });
+            }
+        }
+
+        for (index, rule) in self.common.status_mapping.iter().flatten().enumerate() {
+            validate_status_mapping(
+                rule,
+                &None,
+                &format!("common.status_mapping[{}]", index),
+                &mut errors,
+            );
+        }
+
+        if let Some(docs) = &self.common.docs {
+            let path = docs.path.trim_end_matches('/');
+            let prefix = self.common.path_prefix.trim_end_matches('/');
+            let message = if !docs.path.starts_with('/') || path.is_empty() {
+                Some(format!(
+                    "`{}` must start with a `/` and can't be the root",
+                    docs.path
+                ))
+            } else if path == prefix || path.starts_with(&format!("{}/", prefix)) {
+                Some(format!(
+                    "`{}` can't be under the `path_prefix` `{}`",
+                    docs.path, self.common.path_prefix
+                ))
+            } else {
+                None
+            };
+            if let Some(message) = message {
+                errors.push(ValidationError {
+                    file: None,
+                    key_path: "common.docs.path".to_string(),
+                    message,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Endpoint {
+    /// Returns the file the endpoint was defined in and its key path within that file, falling back to its index in the merged list
+    pub(crate) fn location(&self, index: usize) -> (Option<PathBuf>, String) {
+        match &self.source {
+            Some(source) => (
+                Some(source.file.clone()),
+                format!("endpoints[{}]", source.index),
+            ),
+            None => (None, format!("endpoints[{}]", index)),
+        }
+    }
+}
+
+fn validate_endpoint(
+    endpoint: &Endpoint,
+    file: &Option<PathBuf>,
+    key_path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    if !endpoint.path.starts_with('/') {
+        errors.push(ValidationError {
+            file: file.clone(),
+            key_path: format!("{}.path", key_path),
+            message: format!("`{}` must start with a `/`", endpoint.path),
+        });
+    }
+
+    if endpoint.pq_id.is_empty() {
+        // Endpoints with an `operation_name` that couldn't be resolved have already been reported
+        if endpoint.operation_name.is_none() {
+            errors.push(ValidationError {
+                file: file.clone(),
+                key_path: key_path.to_string(),
+                message: "either `pq_id` or `operation_name` must be set".to_string(),
+            });
+        }
+    } else if !is_sha256(&endpoint.pq_id) {
+        errors.push(ValidationError {
+            file: file.clone(),
+            key_path: format!("{}.pq_id", key_path),
+            message: format!(
+                "`{}` is not a SHA-256 hash; expected 64 hexadecimal characters",
+                endpoint.pq_id
+            ),
+        });
+    }
+
+    for (index, rule) in endpoint.status_mapping.iter().flatten().enumerate() {
+        validate_status_mapping(
+            rule,
+            file,
+            &format!("{}.status_mapping[{}]", key_path, index),
+            errors,
+        );
+    }
+
+    // JSON Pointers are either empty, for the whole document, or start with a `/`
+    let mut pointers = Vec::new();
+    if let Some(response) = &endpoint.response {
+        if let Some(select) = &response.select {
+            pointers.push((format!("{}.response.select", key_path), select));
+        }
+        for (index, transform) in response.transforms.iter().enumerate() {
+            if let Transform::AddFields { at, .. } = transform {
+                pointers.push((
+                    format!("{}.response.transforms[{}].at", key_path, index),
+                    at,
+                ));
+            }
+        }
+    }
+    for (pointer_path, pointer) in pointers {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            errors.push(ValidationError {
+                file: file.clone(),
+                key_path: pointer_path,
+                message: format!(
+                    "`{}` is not a JSON Pointer; it must be empty or start with a `/`, e.g. `/data/user`",
+                    pointer
+                ),
+            });
+        }
+    }
+
+    let segments = path_argument_names(&endpoint.path);
+    for (index, param) in endpoint.path_arguments.iter().flatten().enumerate() {
+        if !segments.contains(&param.from.as_str()) {
+            errors.push(ValidationError {
+                file: file.clone(),
+                key_path: format!("{}.path_arguments[{}]", key_path, index),
+                message: format!(
+                    "`{}` does not contain a `:{}` segment",
+                    endpoint.path, param.from
+                ),
+            });
+        }
+    }
+
+    // Every parameter ends up in the variables of the same GraphQL request, so two parameters can't set the same value.
+    // Parameters can set different fields of the same input object, but not the object itself as well as a field within it
+    // Each entry is the target path, the `to` it came from, and the key paths of the parameter and of its `to`
+    let mut entries = Vec::<(Vec<String>, Option<&str>, String, String)>::new();
+    let sources = [
+        ("path_arguments", &endpoint.path_arguments),
+        ("query_params", &endpoint.query_params),
+        ("body_params", &endpoint.body_params),
+    ];
+    for (source, params) in sources {
+        for (index, param) in params.iter().flatten().enumerate() {
+            let param_path = format!("{}.{}[{}]", key_path, source, index);
+            validate_parameter(param, source, file, &param_path, errors);
+            let to_path = format!("{}.to", param_path);
+            entries.push((
+                param.variable_path(),
+                param.to.as_deref(),
+                param_path,
+                to_path,
+            ));
+        }
+    }
+    if let Some(body) = &endpoint.body {
+        let body_path = format!("{}.body", key_path);
+        let problems = [
+            (
+                body.to.is_some() && body.spread,
+                "`to` and `spread` can't both be set",
+            ),
+            (
+                body.to.is_none() && !body.spread,
+                "either `to` or `spread: true` must be set",
+            ),
+            (
+                endpoint.body_params.is_some(),
+                "`body` and `body_params` can't both be set; `body` already passes the whole request body",
+            ),
+            (
+                body.allow.is_some() && !body.spread,
+                "`allow` only applies with `spread: true`",
+            ),
+        ];
+        for (_, message) in problems.into_iter().filter(|(found, _)| *found) {
+            errors.push(ValidationError {
+                file: file.clone(),
+                key_path: body_path.clone(),
+                message: message.to_string(),
+            });
+        }
+        if let Some(to) = &body.to {
+            let to_path = format!("{}.to", body_path);
+            entries.push((variable_path(to), Some(to), to_path.clone(), to_path));
+        }
+        // Allowed keys are set as variables of the same name, so they can't also be set by a parameter
+        let allow = body.allow.iter().flatten().filter(|_| body.spread);
+        for (index, key) in allow.enumerate() {
+            let allow_path = format!("{}.allow[{}]", body_path, index);
+            entries.push((vec![key.clone()], None, allow_path.clone(), allow_path));
+        }
+    }
+
+    let mut targets = Vec::<(Vec<String>, String)>::new();
+    for (path, to, param_path, to_path) in entries {
+        if path.iter().any(String::is_empty) {
+            errors.push(ValidationError {
+                file: file.clone(),
+                key_path: to_path,
+                message: format!(
+                    "`{}` is not a valid variable path; expected a dotted path such as `input.filter.status` or a JSON Pointer such as `/input/filter/status`",
+                    to.unwrap_or_default()
+                ),
+            });
+            continue;
+        }
+        let conflict = targets
+            .iter()
+            .find(|(other, _)| other.starts_with(&path) || path.starts_with(other));
+        match conflict {
+            Some((other, other_path)) if *other == path => errors.push(ValidationError {
+                file: file.clone(),
+                key_path: param_path,
+                message: format!(
+                    "the variable `{}` is already set by `{}`",
+                    path.join("."),
+                    other_path
+                ),
+            }),
+            Some((other, other_path)) => errors.push(ValidationError {
+                file: file.clone(),
+                key_path: param_path,
+                message: format!(
+                    "`{}` conflicts with `{}` set by `{}`; a value can't be set as a whole as well as by its fields",
+                    path.join("."),
+                    other.join("."),
+                    other_path
+                ),
+            }),
+            None => targets.push((path, param_path)),
+        }
+    }
+}
+
+/// Checks that a parameter's constraints apply to its kind and can be met
+fn validate_parameter(
+    param: &Parameter,
+    source: &str,
+    file: &Option<PathBuf>,
+    key_path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let mut error = |key: &str, message: String| {
+        errors.push(ValidationError {
+            file: file.clone(),
+            key_path: format!("{}.{}", key_path, key),
+            message,
+        })
+    };
+
+    let numeric = matches!(param.kind, ParamKind::INT | ParamKind::FLOAT);
+    let kinds = [
+        (
+            "minimum",
+            param.minimum.is_some(),
+            numeric,
+            "`int` and `float`",
+        ),
+        (
+            "maximum",
+            param.maximum.is_some(),
+            numeric,
+            "`int` and `float`",
+        ),
+        (
+            "style",
+            param
+                .style
+                .is_some_and(|style| style != ParamStyle::DeepObject),
+            param.kind == ParamKind::ARRAY,
+            "`array`",
+        ),
+        (
+            "style",
+            param.style == Some(ParamStyle::DeepObject),
+            param.kind == ParamKind::OBJECT,
+            "`object`",
+        ),
+        (
+            "properties",
+            param.properties.is_some(),
+            param.kind == ParamKind::OBJECT,
+            "`object`",
+        ),
+        (
+            "items",
+            param.items.is_some(),
+            param.kind == ParamKind::ARRAY,
+            "`array`",
+        ),
+        (
+            "pattern",
+            param.pattern.is_some(),
+            param.kind == ParamKind::STRING,
+            "`string`",
+        ),
+        (
+            "min_length",
+            param.min_length.is_some(),
+            param.kind == ParamKind::STRING,
+            "`string`",
+        ),
+        (
+            "max_length",
+            param.max_length.is_some(),
+            param.kind == ParamKind::STRING,
+            "`string`",
+        ),
+        (
+            "min_items",
+            param.min_items.is_some(),
+            param.kind == ParamKind::ARRAY,
+            "`array`",
+        ),
+        (
+            "max_items",
+            param.max_items.is_some(),
+            param.kind == ParamKind::ARRAY,
+            "`array`",
+        ),
+    ];
+    for (key, set, applies, kinds) in kinds {
+        if set && !applies {
+            let message = match key {
+                "style" => format!(
+                    "`style: {}` only applies to {} parameters, but the kind is `{}`",
+                    param.style.map(|s| s.to_string()).unwrap_or_default(),
+                    kinds,
+                    param.kind
+                ),
+                _ => format!(
+                    "`{}` only applies to {} parameters, but the kind is `{}`",
+                    key, kinds, param.kind
+                ),
+            };
+            error(key, message);
+        }
+    }
+    for (name, schema) in param.properties.iter().flatten() {
+        validate_value_schema(schema, &format!("properties.{}", name), &mut error);
+    }
+    if let Some(items) = &param.items {
+        validate_value_schema(items, "items", &mut error);
+    }
+
+    if param.style.is_some() && source != "query_params" {
+        error(
+            "style",
+            "`style` only applies to query parameters".to_string(),
+        );
+    }
+    if param.explode.is_some() && param.style.is_none() {
+        error(
+            "explode",
+            "`explode` only applies to parameters with a `style`".to_string(),
+        );
+    }
+    if param.style == Some(ParamStyle::DeepObject) && param.explode == Some(false) {
+        error(
+            "explode",
+            "`style: deep_object` only supports `explode: true`".to_string(),
+        );
+    }
+
+    let ranges = [
+        ("minimum", param.minimum, "maximum", param.maximum),
+        (
+            "min_length",
+            param.min_length.map(|l| l as f64),
+            "max_length",
+            param.max_length.map(|l| l as f64),
+        ),
+        (
+            "min_items",
+            param.min_items.map(|l| l as f64),
+            "max_items",
+            param.max_items.map(|l| l as f64),
+        ),
+    ];
+    for (min_key, min, max_key, max) in ranges {
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                error(
+                    min_key,
+                    format!("`{}` is greater than `{}`", min_key, max_key),
+                );
+            }
+        }
+    }
+
+    if let Some(pattern) = &param.pattern {
+        if let Err(e) = Regex::new(pattern) {
+            error(
+                "pattern",
+                format!("`{}` is not a valid regular expression: {}", pattern, e),
+            );
+        }
+    }
+
+    for (index, value) in param.enum_values.iter().flatten().enumerate() {
+        if !param.kind.matches(value) {
+            error(
+                &format!("enum[{}]", index),
+                format!("`{}` is not a valid `{}` value", value, param.kind),
+            );
+        }
+    }
+
+    if let Some(default) = &param.default {
+        if param.required {
+            error(
+                "default",
+                "a required parameter is never missing, so its `default` is never used".to_string(),
+            );
+        } else if param.null_on_missing {
+            error(
+                "null_on_missing",
+                "`null_on_missing` has no effect on a parameter with a `default`".to_string(),
+            );
+        }
+        if !param.kind.matches(default) {
+            error(
+                "default",
+                format!("`{}` is not a valid `{}` value", default, param.kind),
+            );
+        } else if let Err(reason) = param.check_constraints(default) {
+            error(
+                "default",
+                format!(
+                    "`{}` doesn't meet the parameter's constraints; it {}",
+                    default, reason
+                ),
+            );
+        }
+    }
+}
+
+/// Checks that the `properties` and `items` of a nested schema are only set for objects and arrays
+fn validate_value_schema(
+    schema: &ValueSchema,
+    key_path: &str,
+    error: &mut impl FnMut(&str, String),
+) {
+    if schema.properties.is_some() && schema.kind != ParamKind::OBJECT {
+        error(
+            &format!("{}.properties", key_path),
+            format!(
+                "`properties` only applies to `object` values, but the kind is `{}`",
+                schema.kind
+            ),
+        );
+    }
+    if schema.items.is_some() && schema.kind != ParamKind::ARRAY {
+        error(
+            &format!("{}.items", key_path),
+            format!(
+                "`items` only applies to `array` values, but the kind is `{}`",
+                schema.kind
+            ),
+        );
+    }
+    for (name, property) in schema.properties.iter().flatten() {
+        validate_value_schema(
+            property,
+            &format!("{}.properties.{}", key_path, name),
+            error,
+        );
+    }
+    if let Some(items) = &schema.items {
+        validate_value_schema(items, &format!("{}.items", key_path), error);
+    }
+}
+
+fn validate_status_mapping(
+    rule: &StatusMapping,
+    file: &Option<PathBuf>,
+    key_path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    if rule.code.is_none() && rule.path.is_none() {
+        errors.push(ValidationError {
+            file: file.clone(),
+            key_path: key_path.to_string(),
+            message: "at least one of `code` or `path` must be set".to_string(),
+        });
+    }
+    if !(100..=599).contains(&rule.status) {
+        errors.push(ValidationError {
+            file: file.clone(),
+            key_path: format!("{}.status", key_path),
+            message: format!(
+                "`{}` is not an HTTP status code; expected a number from 100 to 599",
+                rule.status
+            ),
+        });
+    }
+}
+
+/// Returns the names of the `:name` and `*name` segments in a path
+fn path_argument_names(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter_map(|segment| {
+            segment
+                .strip_prefix(':')
+                .or_else(|| segment.strip_prefix('*'))
+        })
+        .collect()
+}
+
+/// Strips the argument names from a path so that `/users/:id` and `/users/:name` compare as equal
+/// Why the router would refuse to register two different paths together, which it does whatever their methods are
+fn route_conflict(path: &str, other: &str) -> Option<&'static str> {
+    if route_shape(path) == route_shape(other) {
+        return Some("routes with the same shape must use the same argument names");
+    }
+    // A catch-all must be the only argument at its position, so `/users/*rest` conflicts with `/users/:id/posts` as well
+    for (segment, other_segment) in path.split('/').zip(other.split('/')) {
+        let dynamic = |s: &str| s.starts_with(':') || s.starts_with('*');
+        if segment.starts_with('*') || other_segment.starts_with('*') {
+            return (dynamic(segment) && dynamic(other_segment))
+                .then_some("a catch-all can't share its position with another argument");
+        }
+        if segment != other_segment && !(segment.starts_with(':') && other_segment.starts_with(':'))
+        {
+            return None;
+        }
+    }
+    None
+}
+
+fn route_shape(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.starts_with(':') {
+                ":"
+            } else if segment.starts_with('*') {
+                "*"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn is_sha256(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::testing::HASH;
+    use crate::config::{parse_config_str, ConfigError};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_validate_valid_config() {
+        let yaml = format!(
+            r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+endpoints:
+    - path: "/users/:id"
+      pq_id: "{HASH}"
+      path_arguments:
+          - from: "id"
+            to: "userId"
+    - path: "/users/:id"
+      method: PATCH
+      pq_id: "{HASH}"
+      path_arguments:
+          - from: "id"
+"#
+        );
+        assert!(parse_config_str(PathBuf::from("config.yaml"), &yaml).is_ok());
+    }
+
+    #[test]
+    fn test_validate_collects_all_errors() {
+        let yaml = format!(
+            r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+endpoints:
+    - path: "/users/:id"
+      pq_id: "123456789"
+      path_arguments:
+          - from: "userId"
+      query_params:
+          - from: "id"
+            to: "userId"
+    - path: "/users/:id"
+      pq_id: "{HASH}"
+    - path: "/users/:name"
+      method: POST
+      pq_id: "{HASH}"
+"#
+        );
+        let errors = match parse_config_str(PathBuf::from("config.yaml"), &yaml) {
+            Err(ConfigError::Invalid { errors, .. }) => errors,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let key_paths: Vec<&str> = errors.iter().map(|e| e.key_path.as_str()).collect();
+        assert_eq!(
+            key_paths,
+            vec![
+                "endpoints[0].pq_id",
+                "endpoints[0].path_arguments[0]",
+                "endpoints[0].query_params[0]",
+                "endpoints[1]",
+                "endpoints[2].path",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_catch_all_routes() {
+        let yaml = format!(
+            r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+endpoints:
+    - path: "/users/:id"
+      pq_id: "{HASH}"
+    - path: "/users/*rest"
+      pq_id: "{HASH}"
+    - path: "/files/*path"
+      pq_id: "{HASH}"
+    - path: "/files/*path"
+      method: POST
+      pq_id: "{HASH}"
+    - path: "/files/:id/versions"
+      method: DELETE
+      pq_id: "{HASH}"
+    - path: "/files/new"
+      method: POST
+      pq_id: "{HASH}"
+"#
+        );
+        let errors = match parse_config_str(PathBuf::from("config.yaml"), &yaml) {
+            Err(ConfigError::Invalid { errors, .. }) => errors,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let messages: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.key_path.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "endpoints[1].path",
+                    "`/users/*rest` overlaps with `/users/:id` from endpoints[0]; a catch-all can't share its position with another argument"
+                ),
+                (
+                    "endpoints[4].path",
+                    "`/files/:id/versions` overlaps with `/files/*path` from endpoints[2]; a catch-all can't share its position with another argument"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_openapi_path() {
+        let config = |openapi_path: &str| {
+            format!(
+                r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+    openapi:
+        path: "{openapi_path}"
+endpoints:
+    - path: "/users/:id"
+      method: POST
+      pq_id: "{HASH}"
+    - path: "/files/*path"
+      pq_id: "{HASH}"
+"#
+            )
+        };
+        let message = |openapi_path: &str| match parse_config_str(
+            PathBuf::from("config.yaml"),
+            &config(openapi_path),
+        ) {
+            Ok(_) => None,
+            Err(ConfigError::Invalid { errors, .. }) => Some(errors[0].message.clone()),
+            Err(e) => panic!("unexpected error: {:?}", e),
+        };
+
+        assert_eq!(message("/users/:id"), None);
+        assert_eq!(
+            message("/users/:x"),
+            Some("`/users/:x` overlaps with `/users/:id` from endpoints[0]; routes with the same shape must use the same argument names".to_string())
+        );
+        assert_eq!(message("/files/openapi.json"), None);
+        assert_eq!(
+            message("/files/:name"),
+            Some("`/files/:name` overlaps with `/files/*path` from endpoints[1]; a catch-all can't share its position with another argument".to_string())
+        );
+        assert_eq!(
+            message("/files/*path"),
+            Some("`/files/*path` overlaps with GET `/files/*path` from endpoints[1]".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_operation_ids() {
+        let yaml = format!(
+            r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+endpoints:
+    - path: "/users/:id"
+      pq_id: "{HASH}"
+    - path: "/users/id"
+      pq_id: "{HASH}"
+    - path: "/users"
+      method: POST
+      operation_id: "get_users_id"
+      pq_id: "{HASH}"
+"#
+        );
+        let errors = match parse_config_str(PathBuf::from("config.yaml"), &yaml) {
+            Err(ConfigError::Invalid { errors, .. }) => errors,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let messages: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.key_path.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "endpoints[1]",
+                    "the generated operation ID `get_users_id` is already used by endpoints[0]; set `operation_id` to make it unique"
+                ),
+                (
+                    "endpoints[2].operation_id",
+                    "the operation ID `get_users_id` is already used by endpoints[0]"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_parameter_constraints() {
+        let yaml = format!(
+            r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+endpoints:
+    - path: "/users"
+      pq_id: "{HASH}"
+      query_params:
+          - from: "order"
+            enum: [ASC, 1]
+          - from: "limit"
+            kind: int
+            minimum: 10
+            maximum: 1
+            pattern: "[0-9]+"
+          - from: "name"
+            pattern: "("
+          - from: "offset"
+            kind: int
+            default: "0"
+          - from: "first"
+            kind: int
+            minimum: 1
+            default: 0
+          - from: "tags"
+            style: form
+          - from: "ids"
+            kind: array
+            explode: true
+          - from: "filter"
+            kind: array
+            style: deep_object
+          - from: "input"
+            kind: object
+            properties:
+                email:
+                    items:
+                        kind: int
+          - from: "where"
+            kind: object
+            style: deep_object
+            explode: false
+"#
+        );
+        let errors = match parse_config_str(PathBuf::from("config.yaml"), &yaml) {
+            Err(ConfigError::Invalid { errors, .. }) => errors,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let key_paths: Vec<&str> = errors.iter().map(|e| e.key_path.as_str()).collect();
+        assert_eq!(
+            key_paths,
+            vec![
+                "endpoints[0].query_params[0].enum[1]",
+                "endpoints[0].query_params[1].pattern",
+                "endpoints[0].query_params[1].minimum",
+                "endpoints[0].query_params[2].pattern",
+                "endpoints[0].query_params[3].default",
+                "endpoints[0].query_params[4].default",
+                "endpoints[0].query_params[5].style",
+                "endpoints[0].query_params[6].explode",
+                "endpoints[0].query_params[7].style",
+                "endpoints[0].query_params[8].properties.email.items",
+                "endpoints[0].query_params[9].explode",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_nested_variables() {
+        let yaml = format!(
+            r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+endpoints:
+    - path: "/users/:id"
+      pq_id: "{HASH}"
+      path_arguments:
+          - from: "id"
+            to: "input.id"
+      query_params:
+          - from: "status"
+            to: "input.filter.status"
+          - from: "sort"
+            to: "/input/sort"
+          - from: "filter"
+            kind: object
+            to: "input.filter"
+          - from: "role"
+            to: "input..role"
+"#
+        );
+        let errors = match parse_config_str(PathBuf::from("config.yaml"), &yaml) {
+            Err(ConfigError::Invalid { errors, .. }) => errors,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let key_paths: Vec<&str> = errors.iter().map(|e| e.key_path.as_str()).collect();
+        assert_eq!(
+            key_paths,
+            vec![
+                "endpoints[0].query_params[2]",
+                "endpoints[0].query_params[3].to",
+            ]
+        );
+        assert_eq!(
+            errors[0].message,
+            "`input.filter` conflicts with `input.filter.status` set by `endpoints[0].query_params[0]`; a value can't be set as a whole as well as by its fields"
+        );
+    }
+
+    #[test]
+    fn test_validate_body() {
+        let yaml = format!(
+            r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+endpoints:
+    - path: "/users"
+      method: POST
+      pq_id: "{HASH}"
+      body:
+          to: "input"
+          spread: true
+    - path: "/users"
+      method: PUT
+      pq_id: "{HASH}"
+      body:
+          to: "input"
+          allow: ["email"]
+      body_params:
+          - from: "name"
+    - path: "/users/:id"
+      method: PATCH
+      pq_id: "{HASH}"
+      path_arguments:
+          - from: "id"
+            to: "input.id"
+      body:
+          to: "input"
+      body_params:
+          - from: "email"
+    - path: "/users/:id"
+      method: POST
+      pq_id: "{HASH}"
+      path_arguments:
+          - from: "id"
+      body:
+          spread: true
+          allow: ["email", "id"]
+    - path: "/accounts"
+      method: POST
+      pq_id: "{HASH}"
+      query_params:
+          - from: "status"
+            to: "filter.status"
+      body:
+          spread: true
+          allow: ["filter"]
+"#
+        );
+        let errors = match parse_config_str(PathBuf::from("config.yaml"), &yaml) {
+            Err(ConfigError::Invalid { errors, .. }) => errors,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let messages: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.key_path.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ("endpoints[0].body", "`to` and `spread` can't both be set"),
+                (
+                    "endpoints[1].body",
+                    "`body` and `body_params` can't both be set; `body` already passes the whole request body"
+                ),
+                ("endpoints[1].body", "`allow` only applies with `spread: true`"),
+                (
+                    "endpoints[2].body",
+                    "`body` and `body_params` can't both be set; `body` already passes the whole request body"
+                ),
+                (
+                    "endpoints[2].body.to",
+                    "`input` conflicts with `input.id` set by `endpoints[2].path_arguments[0]`; a value can't be set as a whole as well as by its fields"
+                ),
+                (
+                    "endpoints[3].body.allow[1]",
+                    "the variable `id` is already set by `endpoints[3].path_arguments[0]`"
+                ),
+                (
+                    "endpoints[4].body.allow[0]",
+                    "`filter` conflicts with `filter.status` set by `endpoints[4].query_params[0]`; a value can't be set as a whole as well as by its fields"
+                ),
+            ]
+        );
+    }
+}
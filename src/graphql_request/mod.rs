@@ -127,6 +127,7 @@ mod tests {
             path_arguments: None,
             query_params: None,
             body_params: None,
+            ..Default::default()
         };
 
         let response = client
@@ -1,74 +1,48 @@
-use crate::config::Parameter;
+use crate::config::{variable_path, ErrorFormat, ParamKind, ParamStyle, Parameter};
+use crate::response::{
+    problem_response, shape_response, upstream_invalid_params, ClientResponse, ErrorHandling,
+    InvalidParam,
+};
 use crate::{config::Endpoint, graphql_request::Client};
 use axum::http::StatusCode;
 use axum::{
-    extract::{Json as ExtractJson, Path, Query, State},
+    extract::{Json as ExtractJson, OriginalUri, Path, Query, State},
     http::HeaderMap,
     response::IntoResponse,
     Json,
 };
 use axum_macros::debug_handler;
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 use std::collections::HashMap;
+use std::fmt;
 use tracing::debug;
 
 #[derive(Clone)]
 pub struct EndpointHandler {
     pub endpoint: Endpoint,
     pub client: Client,
+    pub errors: ErrorHandling,
 }
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct ClientResponse {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    data: Option<Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    errors: Option<Vec<ClientResponseError>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    extensions: Option<Value>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct ClientResponseError {
-    message: String,
-}
-
 #[debug_handler]
 /// The handler function for the endpoints.
 /// Each endpoint uses the same handler function but uses different states to represent the PQ it is serving along with the configuration.
 pub async fn handler(
     headers: HeaderMap,
+    OriginalUri(uri): OriginalUri,
     Path(path_parameters): Path<HashMap<String, String>>,
     State(state): State<EndpointHandler>,
-    Query(query_parameters): Query<HashMap<String, String>>,
+    Query(query_parameters): Query<Vec<(String, String)>>,
     body: Option<ExtractJson<Value>>,
 ) -> impl IntoResponse {
     let mut request_variables = HashMap::<String, Value>::new();
-
-    // If there are query parameters defined within the endpoint configuration, iterate through them
-    let query_variables =
-        match parse_parameters(query_parameters, state.endpoint.query_params.clone()) {
-            Ok(p) => p,
-            Err(e) => return build_error_response(StatusCode::BAD_REQUEST, e),
-        };
-
-    request_variables.extend(query_variables);
-
-    // Repeat the same process for path parameters
-    let path_variables =
-        match parse_parameters(path_parameters, state.endpoint.path_arguments.clone()) {
-            Ok(p) => p,
-            Err(e) => return build_error_response(StatusCode::BAD_REQUEST, e),
-        };
-
-    request_variables.extend(path_variables);
+    let instance = uri.path();
 
     // If the endpoint is configured to use the request body as variables, parse the body
-    let bp = match body {
+    let bp = match &body {
         Some(body) => {
             let mut m = HashMap::<String, String>::new();
             // convert the body to a hashmap of strings since we really only care about top level keys at the moment
-            for (key, val) in body.as_object().unwrap() {
+            for (key, val) in body.as_object().into_iter().flatten() {
                 let value = val.as_str();
                 match value {
                     Some(v) => {
@@ -83,11 +57,75 @@ pub async fn handler(
         }
         None => HashMap::<String, String>::new(),
     };
-    let body_params = match parse_parameters(bp, state.endpoint.body_params.clone()) {
-        Ok(p) => p,
-        Err(e) => return build_error_response(StatusCode::BAD_REQUEST, e),
-    };
-    request_variables.extend(body_params);
+
+    // If the endpoint passes the body as a whole, bind it to a single variable or spread its keys as variables; mapped parameters are merged over it
+    if let (Some(config), Some(ExtractJson(body))) = (&state.endpoint.body, body) {
+        match (&config.to, body) {
+            (Some(to), body) => insert_variable(&mut request_variables, variable_path(to), body),
+            (None, Value::Object(object)) if config.spread => {
+                // Path and query parameters take precedence, even when they are missing from the request, so the body can't set their variables
+                let explicit: Vec<String> =
+                    [&state.endpoint.path_arguments, &state.endpoint.query_params]
+                        .into_iter()
+                        .flatten()
+                        .flatten()
+                        .map(Parameter::variable)
+                        .collect();
+                for (key, value) in object {
+                    let allowed = config
+                        .allow
+                        .as_ref()
+                        .is_none_or(|allow| allow.contains(&key));
+                    if !allowed {
+                        debug!("Ignoring body key not in `allow`: {}", key);
+                    } else if explicit.contains(&key) {
+                        debug!("Ignoring body key set by a parameter: {}", key);
+                    } else {
+                        insert_variable(&mut request_variables, vec![key], value);
+                    }
+                }
+            }
+            (None, _) if config.spread => {
+                return error_response(
+                    &state,
+                    instance,
+                    StatusCode::BAD_REQUEST,
+                    "The request body must be a JSON object".to_string(),
+                    vec![],
+                )
+            }
+            _ => {}
+        }
+    }
+
+    // Parse the query, path and body parameters defined within the endpoint configuration, collecting every error so they can be reported together
+    let sources = [
+        (
+            "query",
+            query_values(query_parameters, &state.endpoint.query_params),
+            &state.endpoint.query_params,
+        ),
+        (
+            "path",
+            single_values(path_parameters),
+            &state.endpoint.path_arguments,
+        ),
+        ("body", single_values(bp), &state.endpoint.body_params),
+    ];
+    let mut parameter_errors = Vec::new();
+    for (location, request_parameters, config_parameters) in sources {
+        match parse_parameters(request_parameters, config_parameters.clone(), location) {
+            Ok(variables) => {
+                for (name, value) in variables {
+                    insert_variable(&mut request_variables, vec![name], value);
+                }
+            }
+            Err(errors) => parameter_errors.extend(errors),
+        }
+    }
+    if !parameter_errors.is_empty() {
+        return parameter_error_response(&state, instance, parameter_errors);
+    }
 
     debug!("Request Parameters: {:?}", request_variables);
     let response = state
@@ -105,27 +143,97 @@ pub async fn handler(
 
             // Remove transfer-encoding header to prevent issues with gzip responses
             headers.remove("transfer-encoding");
+            // The body is re-serialized, and possibly reshaped, so the upstream length no longer applies
+            headers.remove("content-length");
 
             let json = resp.json::<ClientResponse>().await;
             match json {
-                Ok(json) => {
+                Ok(mut json) => {
                     debug!("JSON: {:?}", json);
                     if let Some(ref errors) = json.errors {
-                        // If there are errors in the response, set the status to 500 if the response is 200 or 400; this prioritizes the status returned by the router in non-compliant situations
+                        // If there are errors in the response, map them to a status if the response is 200; this prioritizes the status returned by the router in non-compliant situations.
+                        // Unmapped errors give a 500, or a 206 to indicate partial content per RFC if there is data in the response
                         if status == StatusCode::OK && !errors.is_empty() {
-                            status = StatusCode::INTERNAL_SERVER_ERROR;
-                            // If there is data in the response, set the status to 206 to indicate partial content per RFC
-                            if json.data.is_some() {
-                                status = StatusCode::PARTIAL_CONTENT;
-                            }
+                            status = state.errors.status(errors, json.data.is_some());
+                        }
+                    }
+                    // Redact after mapping the status, since the mapping may rely on fields that are redacted
+                    if let Some(ref mut errors) = json.errors {
+                        state.errors.redact(errors);
+                    }
+                    if let Some(errors) = json.errors.as_deref().filter(|e| !e.is_empty()) {
+                        if state.errors.format == ErrorFormat::ProblemJson
+                            && (status.is_client_error() || status.is_server_error())
+                        {
+                            let detail = errors
+                                .iter()
+                                .map(|e| e.message.as_str())
+                                .collect::<Vec<_>>()
+                                .join("; ");
+                            return problem_response(
+                                status,
+                                detail,
+                                instance,
+                                &upstream_invalid_params(&state.endpoint, errors),
+                                Some(errors),
+                                headers,
+                            );
                         }
                     }
-                    (status, headers, Json(json!(json)))
+                    let body = match &state.endpoint.response {
+                        Some(response) => match shape_response(response, json!(json)) {
+                            Some(body) => body,
+                            None => {
+                                return error_response(
+                                    &state,
+                                    instance,
+                                    StatusCode::NOT_FOUND,
+                                    "Not found".to_string(),
+                                    vec![],
+                                )
+                            }
+                        },
+                        None => json!(json),
+                    };
+                    (status, headers, Json(body))
                 }
-                Err(e) => build_error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+                Err(e) => error_response(
+                    &state,
+                    instance,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    e.to_string(),
+                    vec![],
+                ),
             }
         }
-        Err(e) => build_error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        Err(e) => error_response(
+            &state,
+            instance,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+            vec![],
+        ),
+    }
+}
+
+/// Builds an error response in the endpoint's error format
+fn error_response(
+    state: &EndpointHandler,
+    instance: &str,
+    status: StatusCode,
+    message: String,
+    invalid_params: Vec<InvalidParam>,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    match state.errors.format {
+        ErrorFormat::Graphql => build_error_response(status, message),
+        ErrorFormat::ProblemJson => problem_response(
+            status,
+            message,
+            instance,
+            &invalid_params,
+            None,
+            HeaderMap::new(),
+        ),
     }
 }
 
@@ -147,51 +255,332 @@ fn build_error_response(
     )
 }
 
+/// Builds a 400 response listing every parameter that was missing or invalid, in the endpoint's error format
+fn parameter_error_response(
+    state: &EndpointHandler,
+    instance: &str,
+    errors: Vec<ParameterError>,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    let status = StatusCode::BAD_REQUEST;
+    match state.errors.format {
+        ErrorFormat::Graphql => (
+            status,
+            HeaderMap::new(),
+            Json(json!({
+                "errors": errors
+                    .iter()
+                    .map(|e| json!({ "message": e.to_string(), "extensions": e.invalid_param() }))
+                    .collect::<Vec<_>>(),
+                "data": null,
+            })),
+        ),
+        ErrorFormat::ProblemJson => problem_response(
+            status,
+            errors
+                .iter()
+                .map(ParameterError::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+            instance,
+            &errors
+                .iter()
+                .map(ParameterError::invalid_param)
+                .collect::<Vec<_>>(),
+            None,
+            HeaderMap::new(),
+        ),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// A request parameter that was missing or couldn't be parsed as its kind
+struct ParameterError {
+    name: String,
+    /// Where the parameter was expected; one of `path`, `query` or `body`
+    location: &'static str,
+    kind: ParamKind,
+    /// The value in the request, or `None` if the parameter was missing
+    received: Option<String>,
+    /// Why the value couldn't be parsed or didn't meet the parameter's constraints, or `None` if the parameter was missing
+    error: Option<String>,
+}
+
+impl ParameterError {
+    fn invalid_param(&self) -> InvalidParam {
+        InvalidParam {
+            name: self.name.clone(),
+            location: self.location.to_string(),
+            kind: self.kind,
+            received: self.received.clone(),
+            reason: self
+                .error
+                .clone()
+                .unwrap_or_else(|| "is required".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Some(error) => write!(
+                f,
+                "Invalid value for {} parameter {}: {}",
+                self.location, self.name, error
+            ),
+            None => write!(f, "Missing required parameter: {}", self.name),
+        }
+    }
+}
+
+/// Groups the query string's values by key, so that repeated keys such as `?ids=1&ids=2` keep every value.
+/// Bracketed keys such as `?ids[]=1` are merged with `ids` only for styled arrays; for other parameters they are a different key
+fn query_values(
+    pairs: Vec<(String, String)>,
+    config_parameters: &Option<Vec<Parameter>>,
+) -> HashMap<String, Vec<String>> {
+    let styled_array = |name: &str| {
+        config_parameters.iter().flatten().any(|param| {
+            param.from == name && param.style.is_some() && param.kind == ParamKind::ARRAY
+        })
+    };
+    let mut values = HashMap::<String, Vec<String>>::new();
+    for (key, value) in pairs {
+        let key = match key.strip_suffix("[]") {
+            Some(name) if styled_array(name) => name.to_string(),
+            _ => key,
+        };
+        values.entry(key).or_default().push(value);
+    }
+    values
+}
+
+fn single_values(parameters: HashMap<String, String>) -> HashMap<String, Vec<String>> {
+    parameters
+        .into_iter()
+        .map(|(key, value)| (key, vec![value]))
+        .collect()
+}
+
 fn parse_parameters(
-    request_parameters: HashMap<String, String>,
+    request_parameters: HashMap<String, Vec<String>>,
     config_parameters: Option<Vec<Parameter>>,
-) -> Result<HashMap<String, Value>, String> {
+    location: &'static str,
+) -> Result<HashMap<String, Value>, Vec<ParameterError>> {
     let mut parameters = HashMap::<String, Value>::new();
+    let mut errors = Vec::new();
     if let Some(params) = config_parameters {
         for param in params {
-            if request_parameters.contains_key(param.from.clone().as_str()) {
-                if let Some(value) = request_parameters.get(param.from.as_str()) {
-                    match param.kind.clone().from_str(value) {
-                        Ok(p) => parameters.insert(param.to.unwrap_or(param.from.clone()), p),
-                        Err(e) => return Err(e.to_string()),
-                    };
+            let parsed = match param.style {
+                Some(ParamStyle::DeepObject) => parse_deep_object(&param, &request_parameters),
+                _ => request_parameters
+                    .get(param.from.as_str())
+                    .map(|values| parse_value(&param, values)),
+            };
+            if let Some(parsed) = parsed {
+                match parsed {
+                    Ok(p) => {
+                        insert_variable(&mut parameters, param.variable_path(), p);
+                    }
+                    Err((received, e)) => errors.push(ParameterError {
+                        name: param.from,
+                        location,
+                        kind: param.kind,
+                        received: Some(received),
+                        error: Some(e),
+                    }),
                 }
             } else if param.required {
-                return Err(format!("Missing required parameter: {}", param.from));
+                errors.push(ParameterError {
+                    name: param.from,
+                    location,
+                    kind: param.kind,
+                    received: None,
+                    error: None,
+                });
+            } else if let Some(default) = param.default.clone() {
+                insert_variable(&mut parameters, param.variable_path(), default);
+            } else if param.null_on_missing {
+                insert_variable(&mut parameters, param.variable_path(), Value::Null);
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(parameters)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses a parameter's values from the request and checks them against its constraints.
+/// On failure, returns the rejected value along with why it was rejected
+fn parse_value(param: &Parameter, values: &[String]) -> Result<Value, (String, String)> {
+    // Repeated keys only make sense for styled arrays; otherwise the last value wins
+    let last = values.last().map(String::as_str).unwrap_or_default();
+    let (value, received) = match param.style {
+        Some(style) if param.kind == ParamKind::ARRAY => {
+            let (items, received): (Vec<&str>, _) = if param.explode() {
+                (
+                    values.iter().map(String::as_str).collect(),
+                    values.join(","),
+                )
+            } else if last.is_empty() {
+                (vec![], last.to_string())
+            } else {
+                let delimiter = match style {
+                    ParamStyle::SpaceDelimited => ' ',
+                    ParamStyle::PipeDelimited => '|',
+                    // Deep objects are parsed by `parse_deep_object`, so they never get here
+                    ParamStyle::Form | ParamStyle::DeepObject => ',',
+                };
+                (last.split(delimiter).collect(), last.to_string())
+            };
+            let kind = param.items.as_ref().map_or(ParamKind::STRING, |i| i.kind);
+            let items = items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    kind.from_str(item).map_err(|e| {
+                        (
+                            item.to_string(),
+                            format!("item {}: expected {} ({})", index, kind, e),
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            (Value::Array(items), received)
+        }
+        _ => {
+            let value = param
+                .kind
+                .from_str(last)
+                .map_err(|e| (last.to_string(), format!("expected {} ({})", param.kind, e)))?;
+            (value, last.to_string())
+        }
+    };
+    param.check_constraints(&value).map_err(|e| (received, e))?;
+    Ok(value)
+}
+
+/// Builds an object from bracketed query keys such as `filter[owner][id]`, parsing each field as the kind in the parameter's `properties`.
+/// Returns `None` if the request has no keys for the parameter
+fn parse_deep_object(
+    param: &Parameter,
+    request_parameters: &HashMap<String, Vec<String>>,
+) -> Option<Result<Value, (String, String)>> {
+    let prefix = format!("{}[", param.from);
+    let mut fields = HashMap::<String, Value>::new();
+    // Keys are sorted, so `filter[owner]` is seen before `filter[owner][id]`
+    let mut seen = Vec::<(Vec<String>, &String)>::new();
+    let mut keys: Vec<&String> = request_parameters.keys().collect();
+    keys.sort();
+    for key in keys {
+        let Some(path) = key
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(']'))
+        else {
+            continue;
+        };
+        let path: Vec<String> = path.split("][").map(str::to_string).collect();
+        let value = request_parameters[key]
+            .last()
+            .map(String::as_str)
+            .unwrap_or_default();
+        // A field can't be set as a value as well as an object, e.g. `filter[owner]=x&filter[owner][id]=5`
+        if let Some((_, other)) = seen.iter().find(|(other, _)| path.starts_with(other)) {
+            return Some(Err((
+                value.to_string(),
+                format!("`{}` conflicts with `{}`", key, other),
+            )));
+        }
+        seen.push((path.clone(), key));
+        let mut properties = param.properties.as_ref();
+        let mut kind = ParamKind::STRING;
+        for segment in &path {
+            let schema = properties.and_then(|p| p.get(segment));
+            kind = schema.map_or(ParamKind::STRING, |s| s.kind);
+            properties = schema.and_then(|s| s.properties.as_ref());
+        }
+        match kind.from_str(value) {
+            Ok(value) => insert_variable(&mut fields, path, value),
+            Err(e) => {
+                return Some(Err((
+                    value.to_string(),
+                    format!("`{}`: expected {} ({})", key, kind, e),
+                )))
             }
         }
     }
-    Ok(parameters)
+    if seen.is_empty() {
+        return None;
+    }
+    let object = Value::Object(fields.into_iter().collect());
+    match param.check_constraints(&object) {
+        Ok(()) => Some(Ok(object)),
+        Err(e) => Some(Err((object.to_string(), e))),
+    }
 }
+
+/// Sets the value at a path of variables such as `["input", "filter", "status"]`, merging it into any object that is already there
+fn insert_variable(variables: &mut HashMap<String, Value>, mut path: Vec<String>, value: Value) {
+    let name = path.remove(0);
+    let value = path.into_iter().rev().fold(value, |value, key| {
+        let mut object = Map::new();
+        object.insert(key, value);
+        Value::Object(object)
+    });
+    match variables.get_mut(&name) {
+        Some(existing) => merge_value(existing, value),
+        None => {
+            variables.insert(name, value);
+        }
+    }
+}
+
+fn merge_value(target: &mut Value, value: Value) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(value)) => {
+            for (key, value) in value {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_value(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::vec;
 
     use axum::body::to_bytes;
+    use axum::http::Uri;
 
     use super::*;
-    use crate::config::{Endpoint, ParamKind, Parameter};
+    use crate::config::{
+        BodyConfig, Endpoint, ParamKind, Parameter, ResponseConfig, ResponseErrors, StatusMapping,
+        ValueSchema,
+    };
     use crate::Client;
 
     #[tokio::test]
     async fn test_parse_parameters() {
-        let request_parameters = vec![("param1".to_string(), "value1".to_string())]
-            .into_iter()
-            .collect();
+        let request_parameters =
+            HashMap::from([("param1".to_string(), vec!["value1".to_string()])]);
 
         let config_parameters = Some(vec![Parameter {
             from: "param1".to_string(),
             to: Some("param1".to_string()),
             kind: ParamKind::STRING,
             required: true,
+            ..Default::default()
         }]);
 
-        let result = parse_parameters(request_parameters, config_parameters);
+        let result = parse_parameters(request_parameters, config_parameters, "query");
         assert!(result.is_ok());
         let result = result.unwrap();
         assert_eq!(result.len(), 1);
@@ -201,6 +590,262 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_parse_parameters_with_defaults() {
+        let config_parameters = Some(vec![
+            Parameter {
+                from: "limit".to_string(),
+                kind: ParamKind::INT,
+                default: Some(json!(20)),
+                ..Default::default()
+            },
+            Parameter {
+                from: "cursor".to_string(),
+                to: Some("after".to_string()),
+                null_on_missing: true,
+                ..Default::default()
+            },
+            Parameter {
+                from: "order".to_string(),
+                ..Default::default()
+            },
+        ]);
+
+        let result = parse_parameters(HashMap::new(), config_parameters.clone(), "query");
+        assert_eq!(
+            result.unwrap(),
+            HashMap::from([
+                ("limit".to_string(), json!(20)),
+                ("after".to_string(), Value::Null),
+            ])
+        );
+
+        let request_parameters = HashMap::from([("limit".to_string(), vec!["5".to_string()])]);
+        let result = parse_parameters(request_parameters, config_parameters, "query");
+        assert_eq!(result.unwrap().get("limit"), Some(&json!(5)));
+    }
+
+    #[tokio::test]
+    async fn test_parse_parameters_with_nested_variables() {
+        let config_parameters = Some(vec![
+            Parameter {
+                from: "status".to_string(),
+                to: Some("input.filter.status".to_string()),
+                ..Default::default()
+            },
+            Parameter {
+                from: "sort".to_string(),
+                to: Some("/input/sort".to_string()),
+                ..Default::default()
+            },
+        ]);
+        let request_parameters = HashMap::from([
+            ("status".to_string(), vec!["ACTIVE".to_string()]),
+            ("sort".to_string(), vec!["NAME".to_string()]),
+        ]);
+
+        let mut variables =
+            parse_parameters(request_parameters, config_parameters, "query").unwrap();
+        assert_eq!(
+            variables,
+            HashMap::from([(
+                "input".to_string(),
+                json!({"filter": {"status": "ACTIVE"}, "sort": "NAME"})
+            )])
+        );
+
+        insert_variable(
+            &mut variables,
+            vec!["input".to_string()],
+            json!({"filter": {"role": "ADMIN"}}),
+        );
+        assert_eq!(
+            variables["input"],
+            json!({"filter": {"status": "ACTIVE", "role": "ADMIN"}, "sort": "NAME"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_parameters_with_styled_arrays() {
+        let array = |from: &str, style: ParamStyle, explode: Option<bool>| Parameter {
+            from: from.to_string(),
+            kind: ParamKind::ARRAY,
+            style: Some(style),
+            explode,
+            items: Some(Box::new(ValueSchema {
+                kind: ParamKind::INT,
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        let config_parameters = Some(vec![
+            array("ids", ParamStyle::Form, None),
+            array("page", ParamStyle::Form, Some(false)),
+            array("sizes", ParamStyle::PipeDelimited, None),
+            Parameter {
+                from: "tag".to_string(),
+                ..Default::default()
+            },
+        ]);
+        let request_parameters = query_values(
+            vec![
+                ("ids".to_string(), "1".to_string()),
+                ("ids[]".to_string(), "2".to_string()),
+                ("page".to_string(), "3,4".to_string()),
+                ("sizes".to_string(), "5|6".to_string()),
+                ("tag".to_string(), "x".to_string()),
+                ("tag[]".to_string(), "y".to_string()),
+            ],
+            &config_parameters,
+        );
+
+        let result = parse_parameters(request_parameters, config_parameters.clone(), "query");
+        assert_eq!(
+            result.unwrap(),
+            HashMap::from([
+                ("ids".to_string(), json!([1, 2])),
+                ("page".to_string(), json!([3, 4])),
+                ("sizes".to_string(), json!([5, 6])),
+                ("tag".to_string(), json!("x")),
+            ])
+        );
+
+        let request_parameters = query_values(
+            vec![("page".to_string(), "3,x".to_string())],
+            &config_parameters,
+        );
+        let result = parse_parameters(request_parameters, config_parameters, "query");
+        assert_eq!(
+            result.unwrap_err(),
+            vec![ParameterError {
+                name: "page".to_string(),
+                location: "query",
+                kind: ParamKind::ARRAY,
+                received: Some("x".to_string()),
+                error: Some("item 1: expected int (invalid digit found in string)".to_string()),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_parameters_with_non_finite_float() {
+        let config_parameters = Some(vec![
+            Parameter {
+                from: "ratio".to_string(),
+                kind: ParamKind::FLOAT,
+                ..Default::default()
+            },
+            Parameter {
+                from: "weights".to_string(),
+                kind: ParamKind::ARRAY,
+                style: Some(ParamStyle::Form),
+                items: Some(Box::new(ValueSchema {
+                    kind: ParamKind::FLOAT,
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+        ]);
+        let request_parameters = query_values(
+            vec![
+                ("ratio".to_string(), "NaN".to_string()),
+                ("weights".to_string(), "1.5".to_string()),
+                ("weights".to_string(), "inf".to_string()),
+            ],
+            &config_parameters,
+        );
+
+        let mut errors =
+            parse_parameters(request_parameters, config_parameters, "query").unwrap_err();
+        errors.sort_by(|a, b| a.name.cmp(&b.name));
+        let reasons: Vec<Option<&str>> = errors.iter().map(|e| e.error.as_deref()).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                Some("expected float (the number must be finite)"),
+                Some("item 1: expected float (the number must be finite)"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_parameters_with_deep_object() {
+        let schema = |kind: ParamKind, properties: Vec<(&str, ValueSchema)>| ValueSchema {
+            kind,
+            properties: Some(
+                properties
+                    .into_iter()
+                    .map(|(name, schema)| (name.to_string(), schema))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let config_parameters = Some(vec![Parameter {
+            from: "filter".to_string(),
+            kind: ParamKind::OBJECT,
+            style: Some(ParamStyle::DeepObject),
+            properties: schema(
+                ParamKind::OBJECT,
+                vec![
+                    ("active", schema(ParamKind::BOOLEAN, vec![])),
+                    (
+                        "owner",
+                        schema(
+                            ParamKind::OBJECT,
+                            vec![("id", schema(ParamKind::INT, vec![]))],
+                        ),
+                    ),
+                ],
+            )
+            .properties,
+            ..Default::default()
+        }]);
+        let request_parameters = query_values(
+            vec![
+                ("filter[status]".to_string(), "ACTIVE".to_string()),
+                ("filter[active]".to_string(), "true".to_string()),
+                ("filter[owner][id]".to_string(), "5".to_string()),
+                ("other".to_string(), "1".to_string()),
+            ],
+            &config_parameters,
+        );
+
+        let result = parse_parameters(request_parameters, config_parameters.clone(), "query");
+        assert_eq!(
+            result.unwrap(),
+            HashMap::from([(
+                "filter".to_string(),
+                json!({"status": "ACTIVE", "active": true, "owner": {"id": 5}})
+            )])
+        );
+
+        let request_parameters = query_values(
+            vec![("filter[owner][id]".to_string(), "me".to_string())],
+            &config_parameters,
+        );
+        let result = parse_parameters(request_parameters, config_parameters.clone(), "query");
+        assert_eq!(
+            result.unwrap_err()[0].error.as_deref(),
+            Some("`filter[owner][id]`: expected int (invalid digit found in string)")
+        );
+
+        let request_parameters = query_values(
+            vec![
+                ("filter[active]".to_string(), "true".to_string()),
+                ("filter[active][value]".to_string(), "false".to_string()),
+            ],
+            &config_parameters,
+        );
+        let result = parse_parameters(request_parameters, config_parameters.clone(), "query");
+        assert_eq!(
+            result.unwrap_err()[0].error.as_deref(),
+            Some("`filter[active][value]` conflicts with `filter[active]`")
+        );
+
+        let result = parse_parameters(HashMap::new(), config_parameters, "query");
+        assert_eq!(result.unwrap(), HashMap::new());
+    }
+
     #[tokio::test]
     async fn test_parse_parameters_with_missing_required_parameter() {
         let request_parameters = HashMap::new();
@@ -210,11 +855,21 @@ mod tests {
             to: Some("param1".to_string()),
             kind: ParamKind::STRING,
             required: true,
+            ..Default::default()
         }]);
 
-        let result = parse_parameters(request_parameters, config_parameters);
+        let result = parse_parameters(request_parameters, config_parameters, "query");
         assert!(result.is_err());
-        assert_eq!(result.err().unwrap(), "Missing required parameter: param1");
+        assert_eq!(
+            result.err().unwrap(),
+            vec![ParameterError {
+                name: "param1".to_string(),
+                location: "query",
+                kind: ParamKind::STRING,
+                received: None,
+                error: None,
+            }]
+        );
     }
 
     #[tokio::test]
@@ -247,12 +902,18 @@ mod tests {
                 to: Some("param1".to_string()),
                 kind: ParamKind::STRING,
                 required: true,
+                ..Default::default()
             }]),
             body_params: None,
+            ..Default::default()
         };
 
         let client = Client::new(server.url().as_str());
-        let state = EndpointHandler { endpoint, client };
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
 
         let query_parameters = vec![("param1".to_string(), "value1".to_string())]
             .into_iter()
@@ -260,6 +921,7 @@ mod tests {
 
         let (response, body) = handler(
             HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
             Path(vec![].into_iter().collect()),
             State(state),
             Query(query_parameters),
@@ -316,11 +978,17 @@ mod tests {
                 to: Some("param1".to_string()),
                 kind: ParamKind::STRING,
                 required: true,
+                ..Default::default()
             }]),
             body_params: None,
+            ..Default::default()
         };
         let client = Client::new(server.url().as_str());
-        let state = EndpointHandler { endpoint, client };
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
 
         let query_parameters = vec![("param1".to_string(), "value1".to_string())]
             .into_iter()
@@ -328,6 +996,7 @@ mod tests {
 
         let (response, body) = handler(
             HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
             Path(vec![].into_iter().collect()),
             State(state),
             Query(query_parameters),
@@ -379,11 +1048,17 @@ mod tests {
                 to: Some("param1".to_string()),
                 kind: ParamKind::STRING,
                 required: true,
+                ..Default::default()
             }]),
             body_params: None,
+            ..Default::default()
         };
         let client = Client::new(server.url().as_str());
-        let state = EndpointHandler { endpoint, client };
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
 
         let query_parameters = vec![("param1".to_string(), "value1".to_string())]
             .into_iter()
@@ -391,6 +1066,7 @@ mod tests {
 
         let (response, body) = handler(
             HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
             Path(vec![].into_iter().collect()),
             State(state),
             Query(query_parameters),
@@ -408,6 +1084,118 @@ mod tests {
         assert_eq!(body_string, server_body.to_string());
     }
 
+    #[tokio::test]
+    async fn test_handler_with_status_mapping() {
+        let server_body = json!({
+            "data": {"user": null},
+            "errors": [{
+                "message": "User not found",
+                "locations": [{"line": 1, "column": 2}],
+                "path": ["user"],
+                "extensions": {"code": "NOT_FOUND"}
+            }]
+        });
+        let mut server = mockito::Server::new_async().await;
+        let mock_endpoint = server
+            .mock("POST", "/")
+            .with_header("content-type", "application/json")
+            .with_body(server_body.to_string())
+            .create();
+
+        let endpoint = Endpoint {
+            path: "/test".to_string(),
+            pq_id: "test".to_string(),
+            ..Default::default()
+        };
+        let client = Client::new(server.url().as_str());
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling {
+                status_mapping: vec![StatusMapping {
+                    code: Some("NOT_FOUND".to_string()),
+                    path: None,
+                    status: 404,
+                }],
+                redaction: None,
+                format: ErrorFormat::Graphql,
+            },
+        };
+
+        let (response, body) = handler(
+            HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
+            Path(HashMap::new()),
+            State(state),
+            Query(Vec::new()),
+            None,
+        )
+        .await
+        .into_response()
+        .into_parts();
+
+        let body_bytes = to_bytes(body, usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body_bytes).unwrap();
+
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        mock_endpoint.assert();
+        assert_eq!(body, server_body);
+    }
+
+    #[tokio::test]
+    async fn test_handler_with_response_select() {
+        let mut server = mockito::Server::new_async().await;
+        let mock_endpoint = server
+            .mock("POST", "/")
+            .with_header("content-type", "application/json")
+            .with_body(json!({"data": {"user": null}}).to_string())
+            .create();
+
+        let endpoint = Endpoint {
+            path: "/test".to_string(),
+            pq_id: "test".to_string(),
+            response: Some(ResponseConfig {
+                select: Some("/data/user".to_string()),
+                not_found_when_null: true,
+                errors: ResponseErrors::KEEP,
+                transforms: vec![],
+            }),
+            ..Default::default()
+        };
+        let client = Client::new(server.url().as_str());
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
+
+        let (response, body) = handler(
+            HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
+            Path(HashMap::new()),
+            State(state),
+            Query(Vec::new()),
+            None,
+        )
+        .await
+        .into_response()
+        .into_parts();
+
+        let body_bytes = to_bytes(body, usize::MAX).await.unwrap();
+        let body_string = std::str::from_utf8(&body_bytes).unwrap();
+
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        mock_endpoint.assert();
+        assert_eq!(
+            body_string,
+            json!({
+                "errors": [{"message": "Not found"}],
+                "data": null
+            })
+            .to_string()
+        );
+    }
+
     #[tokio::test]
     async fn test_handler_with_missing_required_parameter() {
         let endpoint = Endpoint {
@@ -420,21 +1208,28 @@ mod tests {
                 to: Some("param1".to_string()),
                 kind: ParamKind::STRING,
                 required: true,
+                ..Default::default()
             }]),
             body_params: None,
+            ..Default::default()
         };
 
         let client = Client::new("");
-        let state = EndpointHandler { endpoint, client };
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
 
         let path_parameters = vec![("param1".to_string(), "value1".to_string())]
             .into_iter()
             .collect();
 
-        let query_parameters = HashMap::new();
+        let query_parameters = Vec::new();
 
         let (response, body) = handler(
             HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
             Path(path_parameters),
             State(state),
             Query(query_parameters),
@@ -450,13 +1245,89 @@ mod tests {
         assert_eq!(
             body_string,
             json!({
-                "errors": [{"message": "Missing required parameter: param1"}],
+                "errors": [{
+                    "message": "Missing required parameter: param1",
+                    "extensions": {"name": "param1", "in": "query", "kind": "string", "reason": "is required"}
+                }],
                 "data": null
             })
             .to_string()
         );
     }
 
+    #[tokio::test]
+    async fn test_handler_with_problem_json_errors() {
+        let endpoint = Endpoint {
+            path: "/users/:id".to_string(),
+            pq_id: "test".to_string(),
+            path_arguments: Some(vec![Parameter {
+                from: "id".to_string(),
+                to: None,
+                kind: ParamKind::INT,
+                required: true,
+                ..Default::default()
+            }]),
+            query_params: Some(vec![Parameter {
+                from: "limit".to_string(),
+                to: None,
+                kind: ParamKind::INT,
+                required: true,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        let state = EndpointHandler {
+            endpoint,
+            client: Client::new(""),
+            errors: ErrorHandling {
+                format: ErrorFormat::ProblemJson,
+                ..Default::default()
+            },
+        };
+
+        let (response, body) = handler(
+            HeaderMap::new(),
+            OriginalUri(Uri::from_static("/api/users/abc")),
+            Path(
+                vec![("id".to_string(), "abc".to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            State(state),
+            Query(Vec::new()),
+            None,
+        )
+        .await
+        .into_response()
+        .into_parts();
+
+        let body_bytes = to_bytes(body, usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers["content-type"], "application/problem+json");
+        assert_eq!(
+            body,
+            json!({
+                "type": "about:blank",
+                "title": "Bad Request",
+                "status": 400,
+                "detail": "Missing required parameter: limit; Invalid value for path parameter id: expected int (invalid digit found in string)",
+                "instance": "/api/users/abc",
+                "invalid_params": [
+                    {"name": "limit", "in": "query", "kind": "int", "reason": "is required"},
+                    {
+                        "name": "id",
+                        "in": "path",
+                        "kind": "int",
+                        "received": "abc",
+                        "reason": "expected int (invalid digit found in string)"
+                    }
+                ]
+            })
+        );
+    }
+
     #[tokio::test]
     async fn test_handler_with_invalid_parameter_value() {
         let endpoint = Endpoint {
@@ -468,22 +1339,29 @@ mod tests {
                 to: Some("param1".to_string()),
                 kind: ParamKind::INT,
                 required: true,
+                ..Default::default()
             }]),
             query_params: None,
             body_params: None,
+            ..Default::default()
         };
 
         let client = Client::new("");
-        let state = EndpointHandler { endpoint, client };
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
 
         let path_parameters = vec![("param1".to_string(), "value1".to_string())]
             .into_iter()
             .collect();
 
-        let query_parameters = HashMap::new();
+        let query_parameters = Vec::new();
 
         let (response, body) = handler(
             HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
             Path(path_parameters),
             State(state),
             Query(query_parameters),
@@ -500,7 +1378,16 @@ mod tests {
         assert_eq!(
             body_string,
             json!({
-                "errors": [{"message":"invalid digit found in string"}],
+                "errors": [{
+                    "message": "Invalid value for path parameter param1: expected int (invalid digit found in string)",
+                    "extensions": {
+                        "name": "param1",
+                        "in": "path",
+                        "kind": "int",
+                        "received": "value1",
+                        "reason": "expected int (invalid digit found in string)"
+                    }
+                }],
                 "data": null
             })
             .to_string()
@@ -538,15 +1425,22 @@ mod tests {
                 to: Some("param1".to_string()),
                 kind: ParamKind::STRING,
                 required: true,
+                ..Default::default()
             }]),
+            ..Default::default()
         };
 
         let client = Client::new(server.url().as_str());
-        let state = EndpointHandler { endpoint, client };
-        let query_parameters = HashMap::new();
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
+        let query_parameters = Vec::new();
 
         let (response, body) = handler(
             HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
             Path(vec![].into_iter().collect()),
             State(state),
             Query(query_parameters),
@@ -583,15 +1477,22 @@ mod tests {
                 to: Some("param1".to_string()),
                 kind: ParamKind::STRING,
                 required: true,
+                ..Default::default()
             }]),
+            ..Default::default()
         };
 
         let client = Client::new("");
-        let state = EndpointHandler { endpoint, client };
-        let query_parameters = HashMap::new();
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
+        let query_parameters = Vec::new();
 
         let (response, body) = handler(
             HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
             Path(vec![].into_iter().collect()),
             State(state),
             Query(query_parameters),
@@ -608,10 +1509,219 @@ mod tests {
         assert_eq!(
             body_string,
             json!({
-                "errors": [{"message": "Missing required parameter: param1"}],
+                "errors": [{
+                    "message": "Missing required parameter: param1",
+                    "extensions": {"name": "param1", "in": "body", "kind": "string", "reason": "is required"}
+                }],
                 "data": null
             })
             .to_string()
         );
     }
+
+    #[tokio::test]
+    async fn test_handler_with_body_passthrough() {
+        let mut server = mockito::Server::new_async().await;
+        let mock_endpoint = server
+            .mock("POST", "/")
+            .with_header("content-type", "application/json")
+            .match_body(mockito::Matcher::Json(json!({
+                "variables": {
+                    "id": "42",
+                    "input": {"email": "a@b.c", "tags": ["x"]}
+                },
+                "extensions": {
+                    "persistedQuery": {
+                        "sha256Hash": "test",
+                        "version": 1
+                    }
+                }
+            })))
+            .with_body(json!({"data": "test"}).to_string())
+            .create();
+
+        let endpoint = Endpoint {
+            method: crate::config::HttpMethod::PUT,
+            path: "/test/:id".to_string(),
+            pq_id: "test".to_string(),
+            path_arguments: Some(vec![Parameter {
+                from: "id".to_string(),
+                required: true,
+                ..Default::default()
+            }]),
+            body: Some(BodyConfig {
+                to: Some("input".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let client = Client::new(server.url().as_str());
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
+
+        let (response, _) = handler(
+            HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test/42")),
+            Path(HashMap::from([("id".to_string(), "42".to_string())])),
+            State(state),
+            Query(Vec::new()),
+            Some(Json(json!({"email": "a@b.c", "tags": ["x"]}))),
+        )
+        .await
+        .into_response()
+        .into_parts();
+
+        mock_endpoint.assert();
+        assert_eq!(response.status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_handler_with_spread_body() {
+        let mut server = mockito::Server::new_async().await;
+        let mock_endpoint = server
+            .mock("POST", "/")
+            .with_header("content-type", "application/json")
+            .match_body(mockito::Matcher::Json(json!({
+                "variables": {
+                    "email": "a@b.c",
+                    "name": "Ada"
+                },
+                "extensions": {
+                    "persistedQuery": {
+                        "sha256Hash": "test",
+                        "version": 1
+                    }
+                }
+            })))
+            .with_body(json!({"data": "test"}).to_string())
+            .create();
+
+        let endpoint = Endpoint {
+            method: crate::config::HttpMethod::POST,
+            path: "/test".to_string(),
+            pq_id: "test".to_string(),
+            body: Some(BodyConfig {
+                spread: true,
+                allow: Some(vec!["email".to_string(), "name".to_string()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let client = Client::new(server.url().as_str());
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
+
+        let (response, _) = handler(
+            HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
+            Path(HashMap::new()),
+            State(state.clone()),
+            Query(Vec::new()),
+            Some(Json(
+                json!({"email": "a@b.c", "name": "Ada", "role": "admin"}),
+            )),
+        )
+        .await
+        .into_response()
+        .into_parts();
+
+        mock_endpoint.assert();
+        assert_eq!(response.status, StatusCode::OK);
+
+        let (response, body) = handler(
+            HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test")),
+            Path(HashMap::new()),
+            State(state),
+            Query(Vec::new()),
+            Some(Json(json!(["a@b.c"]))),
+        )
+        .await
+        .into_response()
+        .into_parts();
+
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        let body_bytes = to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(&body_bytes).unwrap(),
+            json!({
+                "errors": [{"message": "The request body must be a JSON object"}],
+                "data": null
+            })
+            .to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handler_with_spread_body_and_parameters() {
+        let mut server = mockito::Server::new_async().await;
+        let mock_endpoint = server
+            .mock("POST", "/")
+            .with_header("content-type", "application/json")
+            .match_body(mockito::Matcher::Json(json!({
+                "variables": {
+                    "id": "42",
+                    "email": "a@b.c"
+                },
+                "extensions": {
+                    "persistedQuery": {
+                        "sha256Hash": "test",
+                        "version": 1
+                    }
+                }
+            })))
+            .with_body(json!({"data": "test"}).to_string())
+            .create();
+
+        let endpoint = Endpoint {
+            method: crate::config::HttpMethod::POST,
+            path: "/test/:id".to_string(),
+            pq_id: "test".to_string(),
+            path_arguments: Some(vec![Parameter {
+                from: "id".to_string(),
+                required: true,
+                ..Default::default()
+            }]),
+            query_params: Some(vec![Parameter {
+                from: "role".to_string(),
+                ..Default::default()
+            }]),
+            body: Some(BodyConfig {
+                spread: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let client = Client::new(server.url().as_str());
+        let state = EndpointHandler {
+            endpoint,
+            client,
+            errors: ErrorHandling::default(),
+        };
+
+        // The body can't override the path argument, nor set `role` even though its query parameter is missing
+        let (response, _) = handler(
+            HeaderMap::new(),
+            OriginalUri(Uri::from_static("/test/42")),
+            Path(HashMap::from([("id".to_string(), "42".to_string())])),
+            State(state),
+            Query(Vec::new()),
+            Some(Json(json!({"id": "7", "email": "a@b.c", "role": "admin"}))),
+        )
+        .await
+        .into_response()
+        .into_parts();
+
+        mock_endpoint.assert();
+        assert_eq!(response.status, StatusCode::OK);
+    }
 }
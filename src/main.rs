@@ -1,25 +1,37 @@
 use crate::{
-    config::{generate_schema, parse_config, HttpMethod, LogLevel},
+    config::{generate_schema, parse_config, Config, HttpMethod, LogLevel},
     handler::handler,
+    openapi::{docs_router, generate_openapi},
+    reload::{Reloader, SharedRouter},
+    response::ErrorHandling,
 };
 use axum::routing::{delete, get, patch, post, put};
-use axum::Router;
-use clap::{Parser, Subcommand};
+use axum::{Json, Router};
+use clap::{Parser, Subcommand, ValueEnum};
 use graphql_request::Client;
 use handler::EndpointHandler;
+use serde_json::json;
 use tracing::{error, info, Level};
 use tracing_subscriber::FmtSubscriber;
 pub mod config;
 pub mod graphql_request;
 pub mod handler;
+pub mod openapi;
+pub mod operation;
+pub mod reload;
+pub mod response;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct CliFlags {
-    /// The configuration file to use; this is required and can be relative.
-    #[clap(long = "config", short, default_value = "config.yaml")]
+    /// The configuration file, or a directory of configuration files, to use; this is required and can be relative.
+    #[clap(long = "config", short, default_value = "config.yaml", global = true)]
     config_path: String,
 
+    /// Reload the configuration whenever the file changes; a SIGHUP always triggers a reload
+    #[clap(long)]
+    watch: bool,
+
     #[clap(subcommand)]
     command: Option<Commands>,
 }
@@ -27,6 +39,20 @@ struct CliFlags {
 #[derive(Subcommand, Debug)]
 enum Commands {
     ConfigSchema,
+    /// Loads and validates the configuration file without starting the server
+    Validate {
+        /// The format to print the validation result in
+        #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Prints an OpenAPI 3.1 document describing the configured endpoints
+    Openapi,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputFormat {
+    Text,
+    Json,
 }
 
 // TODO: Add error handling
@@ -40,13 +66,69 @@ async fn main() {
         Some(Commands::ConfigSchema) => {
             generate_schema();
         }
+        Some(Commands::Validate { format }) => validate_config(opt.config_path.as_str(), format),
+        Some(Commands::Openapi) => print_openapi(opt.config_path.as_str()),
         None => start_proxy(opt).await,
     }
 }
 
+/// Validates the configuration file and exits with a non-zero code if it has any problems
+fn validate_config(config_path: &str, format: OutputFormat) {
+    let result = parse_config(config_path);
+    match format {
+        OutputFormat::Text => match &result {
+            Ok(config) => println!(
+                "{} is valid; found {} endpoint(s)",
+                config_path,
+                config.endpoints.len()
+            ),
+            Err(e) => eprintln!("{}", e),
+        },
+        OutputFormat::Json => {
+            let output = match &result {
+                Ok(config) => json!({
+                    "valid": true,
+                    "file": config_path,
+                    "endpoints": config.endpoints.len(),
+                }),
+                Err(e) => {
+                    let mut output = e.to_json();
+                    output["valid"] = json!(false);
+                    output
+                }
+            };
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+    }
+
+    if let Err(e) = result {
+        std::process::exit(e.exit_code());
+    }
+}
+
+/// Prints the OpenAPI document for the configuration, or the configuration's errors if it is invalid
+fn print_openapi(config_path: &str) {
+    match parse_config(config_path) {
+        Ok(config) => println!(
+            "{}",
+            serde_json::to_string_pretty(&generate_openapi(&config)).unwrap()
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(e.exit_code());
+        }
+    }
+}
+
 async fn start_proxy(args: CliFlags) {
-    // Parse the configuration file and load it
-    let user_config = parse_config(args.config_path.as_str());
+    // Parse the configuration file and load it; logging isn't set up yet so report straight to stderr
+    let user_config = match parse_config(args.config_path.as_str()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(e.exit_code());
+        }
+    };
 
     // Set up logging
     let level = if let Some(logging) = user_config.common.logging.clone() {
@@ -66,25 +148,11 @@ async fn start_proxy(args: CliFlags) {
     // This shouldn't fail, hence the .expect()
     tracing::subscriber::set_global_default(subscriber).expect("setting default logger failed");
 
-    let mut endpoint_routes: Router = Router::new();
-    for endpoint in user_config.clone().endpoints {
-        let endpoint_handler = EndpointHandler {
-            endpoint: endpoint.clone(),
-            client: Client::new(user_config.clone().common.graphql_endpoint.as_str()),
-        };
-        let path = endpoint.path.clone();
-        let func = match endpoint.method {
-            HttpMethod::GET => get(handler).with_state(endpoint_handler),
-            HttpMethod::POST => post(handler).with_state(endpoint_handler),
-            HttpMethod::PUT => put(handler).with_state(endpoint_handler),
-            HttpMethod::PATCH => patch(handler).with_state(endpoint_handler),
-            HttpMethod::DELETE => delete(handler).with_state(endpoint_handler),
-        };
+    let router = SharedRouter::new(build_router(&user_config));
+    let app = router.clone().into_router();
 
-        endpoint_routes = endpoint_routes.route(&path, func);
-    }
-
-    let app = Router::new().nest(&user_config.common.path_prefix, endpoint_routes);
+    let reloader = Reloader::new(args.config_path.as_str(), user_config.clone(), router);
+    tokio::spawn(reloader.run(args.watch));
 
     // Attempt to start the listener on the provided address
     let listener = match tokio::net::TcpListener::bind(user_config.common.listen).await {
@@ -103,3 +171,37 @@ async fn start_proxy(args: CliFlags) {
         }
     }
 }
+
+/// Builds the routes for every endpoint in the configuration, nested under the path prefix
+pub(crate) fn build_router(config: &Config) -> Router {
+    let mut endpoint_routes: Router = Router::new();
+    for endpoint in config.endpoints.clone() {
+        let endpoint_handler = EndpointHandler {
+            endpoint: endpoint.clone(),
+            client: Client::new(config.common.graphql_endpoint.as_str()),
+            errors: ErrorHandling::new(&config.common, &endpoint),
+        };
+        let path = endpoint.path.clone();
+        let func = match endpoint.method {
+            HttpMethod::GET => get(handler).with_state(endpoint_handler),
+            HttpMethod::POST => post(handler).with_state(endpoint_handler),
+            HttpMethod::PUT => put(handler).with_state(endpoint_handler),
+            HttpMethod::PATCH => patch(handler).with_state(endpoint_handler),
+            HttpMethod::DELETE => delete(handler).with_state(endpoint_handler),
+        };
+
+        endpoint_routes = endpoint_routes.route(&path, func);
+    }
+
+    // The document is generated once per build, so it follows the configuration on reload
+    if let Some(path) = config.common.openapi.as_ref().and_then(|o| o.path.clone()) {
+        let document = Json(generate_openapi(config));
+        endpoint_routes = endpoint_routes.route(&path, get(move || async move { document }));
+    }
+
+    let router = Router::new().nest(&config.common.path_prefix, endpoint_routes);
+    match &config.common.docs {
+        Some(docs) => router.merge(docs_router(config, docs)),
+        None => router,
+    }
+}
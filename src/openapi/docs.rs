@@ -0,0 +1,116 @@
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+use utoipa_swagger_ui::Config as SwaggerConfig;
+
+use super::generate_openapi;
+use crate::config::{Config, DocsConfig};
+
+/// Serves Swagger UI for the OpenAPI document of the configuration, using the assets embedded in the binary
+pub(crate) fn docs_router(config: &Config, docs: &DocsConfig) -> Router {
+    let path = docs.path.trim_end_matches('/');
+    let document_url = format!("{}/openapi.json", path);
+    let swagger_config =
+        Arc::new(SwaggerConfig::from(document_url.clone()).try_it_out_enabled(true));
+    let document = Json(generate_openapi(config));
+    let index = format!("{}/", path);
+
+    Router::new()
+        .route(
+            path,
+            get(move || async move { Redirect::permanent(&index) }),
+        )
+        .route(&format!("{}/", path), get(index_file))
+        .route(&format!("{}/*file", path), get(asset_file))
+        .route(&document_url, get(move || async move { document }))
+        .with_state(swagger_config)
+}
+
+async fn index_file(State(config): State<Arc<SwaggerConfig<'static>>>) -> Response {
+    swagger_file("", config)
+}
+
+async fn asset_file(
+    Path(file): Path<String>,
+    State(config): State<Arc<SwaggerConfig<'static>>>,
+) -> Response {
+    swagger_file(&file, config)
+}
+
+fn swagger_file(file: &str, config: Arc<SwaggerConfig<'static>>) -> Response {
+    match utoipa_swagger_ui::serve(file, config) {
+        Ok(Some(file)) => (
+            [(header::CONTENT_TYPE, file.content_type)],
+            file.bytes.into_owned(),
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build_router;
+    use crate::config::parse_config_str;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use std::path::PathBuf;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_docs_router() {
+        let yaml = r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+    docs:
+        path: "/docs"
+endpoints:
+    - path: "/users"
+      pq_id: "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38"
+"#;
+        let config = parse_config_str(PathBuf::from("config.yaml"), yaml).unwrap();
+        let app = build_router(&config);
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app.clone().oneshot(get("/docs")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/docs/");
+
+        let response = app.clone().oneshot(get("/docs/")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html");
+
+        let response = app
+            .clone()
+            .oneshot(get("/docs/swagger-initializer.js"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("/docs/openapi.json"));
+
+        let response = app
+            .clone()
+            .oneshot(get("/docs/openapi.json"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            document["paths"]["/users"]["get"]["operationId"],
+            "get_users"
+        );
+
+        let response = app.oneshot(get("/docs/missing.js")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,578 @@
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use crate::config::{
+    Config, Endpoint, ErrorFormat, HttpMethod, ParamKind, ParamStyle, Parameter, ServerConfig,
+    ValueSchema,
+};
+use crate::response::{transform_schema, ErrorHandling};
+
+mod docs;
+mod response;
+
+pub(crate) use docs::docs_router;
+
+/// Builds an OpenAPI 3.1 document describing every endpoint in the configuration
+pub fn generate_openapi(config: &Config) -> Value {
+    let openapi = config.common.openapi.clone().unwrap_or_default();
+
+    let mut paths = Map::new();
+    for endpoint in &config.endpoints {
+        let path = paths
+            .entry(openapi_path(&endpoint.path))
+            .or_insert_with(|| json!({}));
+        path[method_name(endpoint.method)] = operation(config, endpoint);
+    }
+
+    let mut info = json!({
+        "title": openapi.title,
+        "version": openapi.version,
+    });
+    if let Some(description) = openapi.description {
+        info["description"] = json!(description);
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": info,
+        "servers": [{ "url": config.common.path_prefix }],
+        "paths": paths,
+        "components": {
+            "schemas": {
+                "GraphQLError": {
+                    "type": "object",
+                    "required": ["message"],
+                    "properties": {
+                        "message": { "type": "string" },
+                        "locations": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "line": { "type": "integer" },
+                                    "column": { "type": "integer" },
+                                },
+                            },
+                        },
+                        "path": {
+                            "type": "array",
+                            "items": { "type": ["string", "integer"] },
+                        },
+                        "extensions": { "type": "object" },
+                    },
+                },
+                "ErrorResponse": {
+                    "type": "object",
+                    "required": ["errors"],
+                    "properties": {
+                        "errors": {
+                            "type": "array",
+                            "items": { "$ref": "#/components/schemas/GraphQLError" },
+                        },
+                        "data": { "type": "null" },
+                    },
+                },
+                "ProblemDetails": {
+                    "type": "object",
+                    "required": ["type", "title", "status"],
+                    "properties": {
+                        "type": { "type": "string" },
+                        "title": { "type": "string" },
+                        "status": { "type": "integer" },
+                        "detail": { "type": "string" },
+                        "instance": { "type": "string" },
+                        "invalid_params": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["name", "in", "kind", "reason"],
+                                "properties": {
+                                    "name": { "type": "string" },
+                                    "in": { "type": "string", "enum": ["path", "query", "body"] },
+                                    "kind": {
+                                        "type": "string",
+                                        "enum": ["int", "string", "float", "object", "array", "boolean"],
+                                    },
+                                    "received": { "type": "string" },
+                                    "reason": { "type": "string" },
+                                },
+                            },
+                        },
+                        "errors": {
+                            "type": "array",
+                            "items": { "$ref": "#/components/schemas/GraphQLError" },
+                        },
+                    },
+                },
+            },
+        },
+    })
+}
+
+fn operation(config: &Config, endpoint: &Endpoint) -> Value {
+    let mut operation = Map::new();
+    operation.insert("operationId".to_string(), json!(operation_id(endpoint)));
+    if let Some(summary) = &endpoint.summary {
+        operation.insert("summary".to_string(), json!(summary));
+    }
+    if let Some(description) = &endpoint.description {
+        operation.insert("description".to_string(), json!(description));
+    }
+    if let Some(tags) = &endpoint.tags {
+        operation.insert("tags".to_string(), json!(tags));
+    }
+
+    let mut parameters = Vec::new();
+    for param in endpoint.path_arguments.iter().flatten() {
+        parameters.push(parameter(param, "path"));
+    }
+    for param in endpoint.query_params.iter().flatten() {
+        parameters.push(parameter(param, "query"));
+    }
+    if !parameters.is_empty() {
+        operation.insert("parameters".to_string(), json!(parameters));
+    }
+
+    if let Some(body_params) = &endpoint.body_params {
+        let properties: Map<String, Value> = body_params
+            .iter()
+            .map(|p| (p.from.clone(), parameter_schema(p)))
+            .collect();
+        let required: Vec<&str> = body_params
+            .iter()
+            .filter(|p| p.required)
+            .map(|p| p.from.as_str())
+            .collect();
+        operation.insert(
+            "requestBody".to_string(),
+            json!({
+                "required": !required.is_empty(),
+                "content": {
+                    "application/json": {
+                        "schema": {
+                            "type": "object",
+                            "properties": properties,
+                            "required": required,
+                        },
+                    },
+                },
+            }),
+        );
+    } else if let Some(body) = &endpoint.body {
+        // A body passed as a whole can be any JSON value, while a spread one is an object whose keys may be limited by `allow`
+        let schema = match &body.allow {
+            _ if !body.spread => json!({}),
+            Some(allow) => json!({
+                "type": "object",
+                "properties": allow.iter().map(|key| (key.clone(), json!({}))).collect::<Map<String, Value>>(),
+            }),
+            None => json!({ "type": "object" }),
+        };
+        operation.insert(
+            "requestBody".to_string(),
+            json!({
+                "required": false,
+                "content": { "application/json": { "schema": schema } },
+            }),
+        );
+    }
+
+    operation.insert(
+        "responses".to_string(),
+        responses(&config.common, endpoint, data_schema(config, endpoint)),
+    );
+    Value::Object(operation)
+}
+
+fn parameter(param: &Parameter, location: &str) -> Value {
+    let mut parameter = json!({
+        "name": param.from,
+        "in": location,
+        // Path arguments are always required by OpenAPI, since the route wouldn't match without them
+        "required": location == "path" || param.required,
+    });
+    // Objects and arrays are passed as raw JSON, which OpenAPI describes with `content` rather than `schema`, unless they have a style
+    match param.kind {
+        _ if param.style.is_some() => {
+            let style = match param.style {
+                Some(ParamStyle::SpaceDelimited) => "spaceDelimited",
+                Some(ParamStyle::PipeDelimited) => "pipeDelimited",
+                Some(ParamStyle::DeepObject) => "deepObject",
+                _ => "form",
+            };
+            parameter["style"] = json!(style);
+            parameter["explode"] = json!(param.explode());
+            parameter["schema"] = parameter_schema(param);
+        }
+        ParamKind::OBJECT | ParamKind::ARRAY => {
+            parameter["content"] =
+                json!({ "application/json": { "schema": parameter_schema(param) } });
+        }
+        _ => parameter["schema"] = parameter_schema(param),
+    }
+    parameter
+}
+
+/// The JSON schema for the values a parameter accepts
+pub(crate) fn parameter_schema(param: &Parameter) -> Value {
+    let mut schema = json!({ "type": schema_type(param.kind) });
+    nested_schemas(
+        &mut schema,
+        param.items.as_deref(),
+        param.properties.as_ref(),
+    );
+    if let Some(values) = &param.enum_values {
+        schema["enum"] = json!(values);
+    }
+    if let Some(default) = &param.default {
+        schema["default"] = default.clone();
+    }
+    let constraints = [
+        ("minimum", param.minimum.map(|m| json!(m))),
+        ("maximum", param.maximum.map(|m| json!(m))),
+        ("pattern", param.pattern.as_ref().map(|p| json!(p))),
+        ("minLength", param.min_length.map(|l| json!(l))),
+        ("maxLength", param.max_length.map(|l| json!(l))),
+        ("minItems", param.min_items.map(|l| json!(l))),
+        ("maxItems", param.max_items.map(|l| json!(l))),
+    ];
+    for (key, value) in constraints {
+        if let Some(value) = value {
+            schema[key] = value;
+        }
+    }
+    schema
+}
+
+/// The JSON schema for a value within an `array` or `object` parameter
+fn value_schema(value: &ValueSchema) -> Value {
+    let mut schema = json!({ "type": schema_type(value.kind) });
+    nested_schemas(
+        &mut schema,
+        value.items.as_deref(),
+        value.properties.as_ref(),
+    );
+    schema
+}
+
+/// Adds the schemas of an array's items and an object's properties
+fn nested_schemas(
+    schema: &mut Value,
+    items: Option<&ValueSchema>,
+    properties: Option<&BTreeMap<String, ValueSchema>>,
+) {
+    if let Some(items) = items {
+        schema["items"] = value_schema(items);
+    }
+    if let Some(properties) = properties {
+        schema["properties"] = properties
+            .iter()
+            .map(|(name, property)| (name.clone(), value_schema(property)))
+            .collect();
+        let required: Vec<&String> = properties
+            .iter()
+            .filter(|(_, property)| property.required)
+            .map(|(name, _)| name)
+            .collect();
+        if !required.is_empty() {
+            schema["required"] = json!(required);
+        }
+    }
+}
+
+fn schema_type(kind: ParamKind) -> &'static str {
+    match kind {
+        ParamKind::INT => "integer",
+        ParamKind::STRING => "string",
+        ParamKind::FLOAT => "number",
+        ParamKind::OBJECT => "object",
+        ParamKind::ARRAY => "array",
+        ParamKind::BOOLEAN => "boolean",
+    }
+}
+
+/// The schema of the endpoint's `data`, if its operation and the GraphQL schema are both known
+fn data_schema(config: &Config, endpoint: &Endpoint) -> Value {
+    let (Some(schema), Some(manifest)) = (&config.schema, &config.manifest) else {
+        return json!({});
+    };
+    manifest
+        .operation(&endpoint.pq_id)
+        .and_then(|operation| response::data_schema(schema, &operation.body, &operation.name))
+        .unwrap_or_else(|| json!({}))
+}
+
+fn responses(common: &ServerConfig, endpoint: &Endpoint, mut data: Value) -> Value {
+    if let Some(response) = &endpoint.response {
+        transform_schema(&response.transforms, &mut data);
+    }
+    let errors = ErrorHandling::new(common, endpoint);
+    let error = match errors.format {
+        ErrorFormat::Graphql => json!({
+            "application/json": {
+                "schema": { "$ref": "#/components/schemas/ErrorResponse" },
+            },
+        }),
+        ErrorFormat::ProblemJson => json!({
+            "application/problem+json": {
+                "schema": { "$ref": "#/components/schemas/ProblemDetails" },
+            },
+        }),
+    };
+    let mut success = json!({
+        "type": "object",
+        "required": ["data"],
+        "properties": {
+            "data": data,
+            "errors": {
+                "type": "array",
+                "items": { "$ref": "#/components/schemas/GraphQLError" },
+            },
+            "extensions": { "type": "object" },
+        },
+    });
+    if let Some(pointer) = endpoint.response.as_ref().and_then(|r| r.select.as_deref()) {
+        success = select_schema(&success, pointer);
+    }
+
+    let mut responses = json!({
+        "200": {
+            "description": "The result of the operation",
+            "content": { "application/json": { "schema": success } },
+        },
+        "400": { "description": "A parameter is missing or invalid", "content": error },
+        "500": { "description": "The operation failed", "content": error },
+    });
+    if endpoint
+        .response
+        .as_ref()
+        .is_some_and(|r| r.not_found_when_null)
+    {
+        responses["404"] = json!({ "description": "The result was null", "content": error });
+    }
+    for rule in errors.status_mapping {
+        let status = rule.status.to_string();
+        if responses.get(&status).is_none() {
+            responses[status] = json!({
+                "description": "A GraphQL error mapped by `status_mapping`",
+                "content": error,
+            });
+        }
+    }
+    responses
+}
+
+/// Follows a JSON Pointer through the properties and items of a schema, falling back to an untyped schema if it can't be followed
+fn select_schema(schema: &Value, pointer: &str) -> Value {
+    let mut current = schema;
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        current = match (
+            current.get("properties").and_then(|p| p.get(&token)),
+            current.get("items"),
+        ) {
+            (Some(property), _) => property,
+            (None, Some(items)) if token.parse::<usize>().is_ok() => items,
+            _ => return json!({}),
+        };
+    }
+    current.clone()
+}
+
+/// Converts axum's `:name` and `*name` segments into OpenAPI's `{name}` templates
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            match segment
+                .strip_prefix(':')
+                .or_else(|| segment.strip_prefix('*'))
+            {
+                Some(name) => format!("{{{}}}", name),
+                None => segment.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn method_name(method: HttpMethod) -> &'static str {
+    match method {
+        HttpMethod::GET => "get",
+        HttpMethod::POST => "post",
+        HttpMethod::PUT => "put",
+        HttpMethod::PATCH => "patch",
+        HttpMethod::DELETE => "delete",
+    }
+}
+
+/// The endpoint's `operation_id`, or one built from its method and path
+pub(crate) fn operation_id(endpoint: &Endpoint) -> String {
+    endpoint
+        .operation_id
+        .clone()
+        .unwrap_or_else(|| default_operation_id(endpoint))
+}
+
+/// Builds an operation ID from the method and path; e.g. `get_users_id` for `GET /users/:id`
+fn default_operation_id(endpoint: &Endpoint) -> String {
+    let mut id = method_name(endpoint.method).to_string();
+    for segment in endpoint.path.split('/') {
+        let segment = segment.trim_start_matches([':', '*']);
+        if !segment.is_empty() {
+            id.push('_');
+            id.extend(
+                segment
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }),
+            );
+        }
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config_str;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_openapi_path() {
+        assert_eq!(openapi_path("/users/:id/posts"), "/users/{id}/posts");
+        assert_eq!(openapi_path("/files/*path"), "/files/{path}");
+    }
+
+    #[test]
+    fn test_select_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "data": {
+                    "type": "object",
+                    "properties": {
+                        "users": {"type": "array", "items": {"type": "object", "properties": {"a/b": {"type": "string"}}}},
+                    },
+                },
+            },
+        });
+        assert_eq!(
+            select_schema(&schema, "/data/users/0/a~1b"),
+            json!({"type": "string"})
+        );
+        assert_eq!(select_schema(&schema, "/data/missing"), json!({}));
+        assert_eq!(select_schema(&schema, ""), schema);
+    }
+
+    #[test]
+    fn test_generate_openapi() {
+        let yaml = r#"
+common:
+    graphql_endpoint: "http://localhost:4000/"
+    openapi:
+        title: "Users API"
+endpoints:
+    - path: "/users/:id"
+      pq_id: "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38"
+      summary: "Get a user"
+      tags: ["users"]
+      path_arguments:
+          - from: "id"
+            kind: int
+            minimum: 1
+      query_params:
+          - from: "fields"
+            kind: array
+            max_items: 10
+          - from: "ids"
+            kind: array
+            style: form
+            items:
+                kind: int
+          - from: "filter"
+            kind: object
+            style: deep_object
+            properties:
+                owner:
+                    kind: object
+                    properties:
+                        id:
+                            kind: int
+                            required: true
+    - path: "/users/:id"
+      method: PATCH
+      operation_id: "updateUser"
+      pq_id: "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38"
+      path_arguments:
+          - from: "id"
+      body_params:
+          - from: "email"
+            required: true
+          - from: "notify"
+            kind: boolean
+    - path: "/users"
+      method: POST
+      pq_id: "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38"
+      body:
+          spread: true
+          allow: ["email", "name"]
+"#;
+        let config = parse_config_str(PathBuf::from("config.yaml"), yaml).unwrap();
+        let document = generate_openapi(&config);
+
+        assert_eq!(document["info"]["title"], "Users API");
+        assert_eq!(document["servers"][0]["url"], "/api/v1");
+
+        let get = &document["paths"]["/users/{id}"]["get"];
+        assert_eq!(get["operationId"], "get_users_id");
+        assert_eq!(get["summary"], "Get a user");
+        assert_eq!(get["tags"], json!(["users"]));
+        assert_eq!(
+            get["parameters"],
+            json!([
+                {"name": "id", "in": "path", "required": true, "schema": {"type": "integer", "minimum": 1.0}},
+                {"name": "fields", "in": "query", "required": false, "content": {"application/json": {"schema": {"type": "array", "maxItems": 10}}}},
+                {"name": "ids", "in": "query", "required": false, "style": "form", "explode": true, "schema": {"type": "array", "items": {"type": "integer"}}},
+                {
+                    "name": "filter",
+                    "in": "query",
+                    "required": false,
+                    "style": "deepObject",
+                    "explode": true,
+                    "schema": {
+                        "type": "object",
+                        "properties": {
+                            "owner": {
+                                "type": "object",
+                                "properties": {"id": {"type": "integer"}},
+                                "required": ["id"],
+                            },
+                        },
+                    },
+                },
+            ])
+        );
+
+        let patch = &document["paths"]["/users/{id}"]["patch"];
+        assert_eq!(patch["operationId"], "updateUser");
+        assert_eq!(
+            patch["requestBody"]["content"]["application/json"]["schema"],
+            json!({
+                "type": "object",
+                "properties": {"email": {"type": "string"}, "notify": {"type": "boolean"}},
+                "required": ["email"],
+            })
+        );
+
+        let post = &document["paths"]["/users"]["post"];
+        assert_eq!(
+            post["requestBody"],
+            json!({
+                "required": false,
+                "content": {"application/json": {"schema": {
+                    "type": "object",
+                    "properties": {"email": {}, "name": {}},
+                }}},
+            })
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use apollo_compiler::ast::Type;
+use apollo_compiler::executable::{Field, Selection, SelectionSet};
+use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::validation::Valid;
+use apollo_compiler::{ExecutableDocument, Node, Schema};
+use serde_json::{json, Map, Value};
+
+/// Builds the JSON schema of an operation's `data` by walking its selection set against the schema.
+/// Returns `None` if the operation isn't valid against the schema, which the configuration checks report separately
+pub(crate) fn data_schema(
+    schema: &Valid<Schema>,
+    body: &str,
+    operation_name: &str,
+) -> Option<Value> {
+    let document =
+        ExecutableDocument::parse_and_validate(schema, body, "operation.graphql").ok()?;
+    let operation = document.operations.get(Some(operation_name)).ok()?;
+    let walker = SelectionWalker {
+        schema,
+        document: &document,
+    };
+    Some(walker.object_schema(&[&operation.selection_set]))
+}
+
+struct SelectionWalker<'a> {
+    schema: &'a Schema,
+    document: &'a ExecutableDocument,
+}
+
+/// The fields selected under a single response key, and whether the key is always present
+struct CollectedField<'a> {
+    key: String,
+    fields: Vec<&'a Node<Field>>,
+    always_present: bool,
+}
+
+impl<'a> SelectionWalker<'a> {
+    /// Merges the selection sets into a single object schema, since fields with the same response key are merged in the response
+    fn object_schema(&self, selection_sets: &[&'a SelectionSet]) -> Value {
+        let mut collected = Vec::new();
+        for selection_set in selection_sets {
+            self.collect_fields(selection_set, &selection_set.ty, true, &mut collected);
+        }
+
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for field in collected {
+            let sub_selections: Vec<&SelectionSet> =
+                field.fields.iter().map(|f| &f.selection_set).collect();
+            properties.insert(
+                field.key.clone(),
+                self.type_schema(field.fields[0].ty(), &sub_selections),
+            );
+            if field.always_present {
+                required.push(field.key);
+            }
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+
+    /// Collects the fields of a selection set by response key, following fragments.
+    /// Fields behind `@skip`, `@include` or a fragment on a narrower type may be absent from the response
+    fn collect_fields(
+        &self,
+        selection_set: &'a SelectionSet,
+        parent_type: &str,
+        always_present: bool,
+        collected: &mut Vec<CollectedField<'a>>,
+    ) {
+        for selection in &selection_set.selections {
+            let directives = selection.directives();
+            let always_present =
+                always_present && !directives.has("skip") && !directives.has("include");
+            match selection {
+                Selection::Field(field) => {
+                    let key = field.response_key().to_string();
+                    match collected.iter_mut().find(|c| c.key == key) {
+                        Some(existing) => {
+                            existing.fields.push(field);
+                            existing.always_present |= always_present;
+                        }
+                        None => collected.push(CollectedField {
+                            key,
+                            fields: vec![field],
+                            always_present,
+                        }),
+                    }
+                }
+                Selection::FragmentSpread(spread) => {
+                    if let Some(fragment) = spread.fragment_def(self.document) {
+                        let applies = fragment.type_condition() == parent_type;
+                        self.collect_fields(
+                            &fragment.selection_set,
+                            parent_type,
+                            always_present && applies,
+                            collected,
+                        );
+                    }
+                }
+                Selection::InlineFragment(inline) => {
+                    let applies = inline
+                        .type_condition
+                        .as_ref()
+                        .is_none_or(|t| t == parent_type);
+                    self.collect_fields(
+                        &inline.selection_set,
+                        parent_type,
+                        always_present && applies,
+                        collected,
+                    );
+                }
+            }
+        }
+    }
+
+    fn type_schema(&self, ty: &Type, selection_sets: &[&'a SelectionSet]) -> Value {
+        match ty {
+            Type::Named(name) => nullable(self.named_type_schema(name, selection_sets)),
+            Type::NonNullNamed(name) => self.named_type_schema(name, selection_sets),
+            Type::List(item) => nullable(json!({
+                "type": "array",
+                "items": self.type_schema(item, selection_sets),
+            })),
+            Type::NonNullList(item) => json!({
+                "type": "array",
+                "items": self.type_schema(item, selection_sets),
+            }),
+        }
+    }
+
+    fn named_type_schema(&self, name: &str, selection_sets: &[&'a SelectionSet]) -> Value {
+        match name {
+            "Int" => json!({ "type": "integer" }),
+            "Float" => json!({ "type": "number" }),
+            "String" | "ID" => json!({ "type": "string" }),
+            "Boolean" => json!({ "type": "boolean" }),
+            _ => match self.schema.types.get(name) {
+                Some(ExtendedType::Enum(definition)) => json!({
+                    "type": "string",
+                    "enum": definition.values.keys().map(|v| v.as_str()).collect::<Vec<_>>(),
+                }),
+                Some(
+                    ExtendedType::Object(_) | ExtendedType::Interface(_) | ExtendedType::Union(_),
+                ) => self.object_schema(selection_sets),
+                // Custom scalars can be serialized as any JSON value
+                _ => json!({}),
+            },
+        }
+    }
+}
+
+/// Allows `null` in addition to the values the schema already accepts
+fn nullable(mut schema: Value) -> Value {
+    if let Some(Value::Array(values)) = schema.get_mut("enum") {
+        values.push(Value::Null);
+    }
+    match schema.get("type").cloned() {
+        Some(Value::String(ty)) => {
+            schema["type"] = json!([ty, "null"]);
+            schema
+        }
+        // Schemas without a type already accept `null`
+        _ => schema,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_schema() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { user(id: ID!): User }
+            type User { id: ID!, name: String, role: Role!, posts: [Post!]!, avatar: Url }
+            type Post { id: ID!, score: Float }
+            enum Role { ADMIN, MEMBER }
+            scalar Url
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let body = r#"
+            query GetUser($id: ID!, $withPosts: Boolean!) {
+                user(id: $id) {
+                    ...UserFields
+                    role
+                    avatar
+                    posts @include(if: $withPosts) { id }
+                    posts @include(if: $withPosts) { score }
+                }
+            }
+            fragment UserFields on User { id displayName: name }
+        "#;
+
+        assert_eq!(
+            data_schema(&schema, body, "GetUser").unwrap(),
+            json!({
+                "type": "object",
+                "required": ["user"],
+                "properties": {
+                    "user": {
+                        "type": ["object", "null"],
+                        "required": ["id", "displayName", "role", "avatar"],
+                        "properties": {
+                            "id": {"type": "string"},
+                            "displayName": {"type": ["string", "null"]},
+                            "role": {"type": "string", "enum": ["ADMIN", "MEMBER"]},
+                            "avatar": {},
+                            "posts": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["id", "score"],
+                                    "properties": {
+                                        "id": {"type": "string"},
+                                        "score": {"type": ["number", "null"]},
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+            })
+        );
+        assert_eq!(
+            data_schema(&schema, "query GetUser { missing }", "GetUser"),
+            None
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use apollo_compiler::ast::{Definition, Document, Type};
+use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::Schema;
+
+use crate::config::ParamKind;
+
+#[derive(Debug, PartialEq, Clone)]
+/// A variable declared by a persisted operation; e.g. `$limit: Int = 10`
+pub struct OperationVariable {
+    pub name: String,
+    pub ty: Type,
+    pub has_default: bool,
+}
+
+impl OperationVariable {
+    /// A variable must be provided if it is non-null and has no default value
+    pub fn required(&self) -> bool {
+        self.ty.is_non_null() && !self.has_default
+    }
+
+    /// The parameter kind that best matches the variable's type.
+    /// Without a schema only the built-in scalars can be identified, so this is `None` for other named types, which may be input objects
+    pub fn kind(&self, schema: Option<&Schema>) -> Option<ParamKind> {
+        if self.ty.is_list() {
+            return Some(ParamKind::ARRAY);
+        }
+        match self.ty.inner_named_type().as_str() {
+            "Int" => Some(ParamKind::INT),
+            "Float" => Some(ParamKind::FLOAT),
+            "Boolean" => Some(ParamKind::BOOLEAN),
+            "String" | "ID" => Some(ParamKind::STRING),
+            name => match schema?.types.get(name) {
+                Some(ExtendedType::InputObject(_)) => Some(ParamKind::OBJECT),
+                _ => Some(ParamKind::STRING),
+            },
+        }
+    }
+
+    /// Whether a parameter of the given kind can be used for this variable.
+    /// Lists also accept a single item, following GraphQL's input coercion rules, and custom scalars accept any kind
+    pub fn accepts(&self, kind: ParamKind, schema: &Schema) -> bool {
+        if self.ty.is_list() && kind == ParamKind::ARRAY {
+            return true;
+        }
+        match self.ty.inner_named_type().as_str() {
+            "Int" => kind == ParamKind::INT,
+            "Float" => matches!(kind, ParamKind::FLOAT | ParamKind::INT),
+            "String" => kind == ParamKind::STRING,
+            "ID" => matches!(kind, ParamKind::STRING | ParamKind::INT),
+            "Boolean" => kind == ParamKind::BOOLEAN,
+            name => match schema.types.get(name) {
+                Some(ExtendedType::Enum(_)) => kind == ParamKind::STRING,
+                Some(ExtendedType::InputObject(_)) => kind == ParamKind::OBJECT,
+                _ => true,
+            },
+        }
+    }
+}
+
+/// Parses an operation document and returns the variables declared by the named operation, or by the only operation if no name is given
+pub fn operation_variables(
+    body: &str,
+    operation_name: Option<&str>,
+) -> Result<Vec<OperationVariable>, String> {
+    let document = match Document::parse(body, "operation.graphql") {
+        Ok(document) => document,
+        Err(e) => return Err(e.errors.to_string()),
+    };
+
+    let mut operations = document.definitions.iter().filter_map(|d| match d {
+        Definition::OperationDefinition(op) => Some(op),
+        _ => None,
+    });
+    let operation = match operation_name {
+        Some(name) => operations.find(|op| op.name.as_ref().is_some_and(|n| n == name)),
+        None => operations.next(),
+    };
+    let Some(operation) = operation else {
+        return Err(format!(
+            "the document does not contain the operation `{}`",
+            operation_name.unwrap_or_default()
+        ));
+    };
+
+    Ok(operation
+        .variables
+        .iter()
+        .map(|v| OperationVariable {
+            name: v.name.to_string(),
+            ty: (*v.ty).clone(),
+            has_default: v.default_value.is_some(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_variables() {
+        let body = r#"
+            query GetUsers($ids: [ID!]!, $limit: Int = 10, $active: Boolean!, $sort: UserSort) {
+                users(ids: $ids, limit: $limit, active: $active, sort: $sort) { id }
+            }
+            query Other($other: String) { other(value: $other) }
+        "#;
+        let variables = operation_variables(body, Some("GetUsers")).unwrap();
+        let summary: Vec<(&str, Option<ParamKind>, bool)> = variables
+            .iter()
+            .map(|v| (v.name.as_str(), v.kind(None), v.required()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("ids", Some(ParamKind::ARRAY), true),
+                ("limit", Some(ParamKind::INT), false),
+                ("active", Some(ParamKind::BOOLEAN), true),
+                ("sort", None, false),
+            ]
+        );
+
+        assert!(operation_variables(body, Some("Missing")).is_err());
+        assert!(operation_variables("query {", None).is_err());
+    }
+
+    #[test]
+    fn test_kind_with_schema() {
+        let schema = Schema::parse(
+            "type Query { users(filter: UserFilter, sort: UserSort, after: Cursor): [ID] }\ninput UserFilter { name: String }\nenum UserSort { NAME }\nscalar Cursor",
+            "schema.graphql",
+        )
+        .unwrap();
+        let body = "query ($filter: UserFilter, $sort: UserSort, $after: Cursor, $limit: Int) { users(filter: $filter, sort: $sort, after: $after) }";
+        let variables = operation_variables(body, None).unwrap();
+
+        let kinds: Vec<ParamKind> = variables
+            .iter()
+            .filter_map(|v| v.kind(Some(&schema)))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ParamKind::OBJECT,
+                ParamKind::STRING,
+                ParamKind::STRING,
+                ParamKind::INT
+            ]
+        );
+
+        assert!(variables[0].accepts(ParamKind::OBJECT, &schema));
+        assert!(!variables[0].accepts(ParamKind::STRING, &schema));
+        assert!(variables[1].accepts(ParamKind::STRING, &schema));
+        assert!(!variables[1].accepts(ParamKind::INT, &schema));
+        assert!(variables[2].accepts(ParamKind::BOOLEAN, &schema));
+        assert!(!variables[3].accepts(ParamKind::STRING, &schema));
+    }
+}
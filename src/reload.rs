@@ -0,0 +1,341 @@
+use crate::build_router;
+use crate::config::{parse_config, Config, ConfigError, Endpoint};
+use axum::{extract::Request, Router};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tower::ServiceExt;
+use tracing::{error, info, warn};
+
+/// How often the configuration file is checked for changes when watching is enabled
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+/// A router that can be swapped out while the server is running.
+/// Each request is served by the router that was current when it arrived, so in-flight requests finish on the old configuration.
+pub struct SharedRouter {
+    current: Arc<RwLock<Router>>,
+}
+
+impl SharedRouter {
+    pub fn new(router: Router) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(router)),
+        }
+    }
+
+    pub fn replace(&self, router: Router) {
+        *self.current.write().unwrap() = router;
+    }
+
+    /// Builds the router handed to the server, which forwards every request to the current router
+    pub fn into_router(self) -> Router {
+        Router::new().fallback(move |request: Request| {
+            let router = self.current.read().unwrap().clone();
+            async move {
+                match router.oneshot(request).await {
+                    Ok(response) => response,
+                    Err(e) => match e {},
+                }
+            }
+        })
+    }
+}
+
+/// Reloads the configuration file and swaps in the new routes, keeping the old ones if the new configuration is invalid
+pub struct Reloader {
+    config_path: String,
+    config: Config,
+    router: SharedRouter,
+}
+
+impl Reloader {
+    pub fn new(config_path: &str, config: Config, router: SharedRouter) -> Self {
+        Self {
+            config_path: config_path.to_string(),
+            config,
+            router,
+        }
+    }
+
+    pub fn reload(&mut self) -> Result<EndpointChanges, ConfigError> {
+        let mut config = parse_config(self.config_path.as_str())?;
+
+        if config.common.listen != self.config.common.listen {
+            warn!("Changes to `common.listen` require a restart and have been ignored");
+        }
+        if config.common.logging != self.config.common.logging {
+            warn!("Changes to `common.logging` require a restart and have been ignored");
+        }
+
+        // The router panics on routes it can't register, which would otherwise end the reload task
+        let router =
+            panic::catch_unwind(AssertUnwindSafe(|| build_router(&config))).map_err(|payload| {
+                ConfigError::Routes {
+                    path: PathBuf::from(&self.config_path),
+                    message: panic_message(payload),
+                }
+            })?;
+        let changes = EndpointChanges::between(&self.config.endpoints, &config.endpoints);
+        self.router.replace(router);
+        // Keep the values the process is running with, so later reloads are compared against them
+        config.common.listen = self.config.common.listen.clone();
+        config.common.logging = self.config.common.logging.clone();
+        self.config = config;
+
+        Ok(changes)
+    }
+
+    fn reload_and_log(&mut self) {
+        match self.reload() {
+            Ok(changes) => info!("Reloaded {}: {}", self.config_path, changes),
+            Err(e) => error!("Rejected reload of {}:\n{}", self.config_path, e),
+        }
+    }
+
+    /// Reloads the configuration whenever the process receives a SIGHUP and, if `watch` is set, whenever one of its files is modified
+    pub async fn run(mut self, watch: bool) {
+        let mut modified = modified_times(&self.config.files);
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        let mut hangup = hangup_signal();
+
+        loop {
+            tokio::select! {
+                _ = interval.tick(), if watch => {
+                    if modified_times(&self.config.files) != modified {
+                        self.reload_and_log();
+                        // The reload may have added or removed included files
+                        modified = modified_times(&self.config.files);
+                    }
+                }
+                _ = recv_hangup(&mut hangup) => {
+                    info!("Received SIGHUP; reloading {}", self.config_path);
+                    self.reload_and_log();
+                }
+            }
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "the router panicked".to_string(),
+        },
+    }
+}
+
+fn modified_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| std::fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+#[cfg(unix)]
+type Hangup = Option<tokio::signal::unix::Signal>;
+#[cfg(not(unix))]
+type Hangup = ();
+
+#[cfg(unix)]
+fn hangup_signal() -> Hangup {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::hangup()) {
+        Ok(signal) => Some(signal),
+        Err(e) => {
+            warn!("Unable to listen for SIGHUP: {:?}", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn hangup_signal() -> Hangup {}
+
+#[cfg(unix)]
+async fn recv_hangup(hangup: &mut Hangup) {
+    match hangup {
+        Some(signal) => {
+            signal.recv().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(not(unix))]
+async fn recv_hangup(_: &mut Hangup) {
+    std::future::pending().await
+}
+
+#[derive(Debug, PartialEq, Default)]
+/// The endpoints that differ between two configurations, identified by their method and path
+pub struct EndpointChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl EndpointChanges {
+    pub fn between(old: &[Endpoint], new: &[Endpoint]) -> Self {
+        let key = |e: &Endpoint| format!("{:?} {}", e.method, e.path);
+        let old: BTreeMap<String, &Endpoint> = old.iter().map(|e| (key(e), e)).collect();
+        let new: BTreeMap<String, &Endpoint> = new.iter().map(|e| (key(e), e)).collect();
+
+        let mut changes = EndpointChanges::default();
+        for (route, endpoint) in &new {
+            match old.get(route) {
+                None => changes.added.push(route.clone()),
+                Some(previous) if previous != endpoint => changes.changed.push(route.clone()),
+                Some(_) => {}
+            }
+        }
+        for route in old.keys() {
+            if !new.contains_key(route) {
+                changes.removed.push(route.clone());
+            }
+        }
+
+        changes
+    }
+}
+
+impl fmt::Display for EndpointChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )?;
+        for (label, routes) in [
+            ("added", &self.added),
+            ("removed", &self.removed),
+            ("changed", &self.changed),
+        ] {
+            if !routes.is_empty() {
+                write!(f, "; {}: {}", label, routes.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::testing::{TestDir, HASH};
+    use crate::config::HttpMethod;
+    use axum::{body::Body, http::StatusCode, routing::get};
+
+    fn endpoint(method: HttpMethod, path: &str) -> Endpoint {
+        Endpoint {
+            path: path.to_string(),
+            method,
+            pq_id: "test".to_string(),
+            query_params: None,
+            path_arguments: None,
+            body_params: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_endpoint_changes() {
+        let old = vec![
+            endpoint(HttpMethod::GET, "/users"),
+            endpoint(HttpMethod::GET, "/users/:id"),
+            endpoint(HttpMethod::DELETE, "/users/:id"),
+        ];
+        let mut changed = endpoint(HttpMethod::GET, "/users/:id");
+        changed.pq_id = "other".to_string();
+        let new = vec![
+            endpoint(HttpMethod::GET, "/users"),
+            changed,
+            endpoint(HttpMethod::POST, "/users"),
+        ];
+
+        let changes = EndpointChanges::between(&old, &new);
+        assert_eq!(
+            changes,
+            EndpointChanges {
+                added: vec!["POST /users".to_string()],
+                removed: vec!["DELETE /users/:id".to_string()],
+                changed: vec!["GET /users/:id".to_string()],
+            }
+        );
+        assert_eq!(
+            changes.to_string(),
+            "1 added, 1 removed, 1 changed; added: POST /users; removed: DELETE /users/:id; changed: GET /users/:id"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shared_router_replace() {
+        let shared = SharedRouter::new(Router::new().route("/old", get(|| async { "old" })));
+        let app = shared.clone().into_router();
+
+        let request = || Request::builder().uri("/old").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        shared.replace(Router::new().route("/new", get(|| async { "new" })));
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_reload_keeps_running_listen() {
+        let config = |listen: &str, path: &str| {
+            format!("common:\n  listen: {listen}\n  graphql_endpoint: http://localhost/\nendpoints:\n  - path: {path}\n    pq_id: {HASH}\n")
+        };
+        let dir = TestDir::new("reload", &[("config.yaml", config("0.0.0.0:3000", "/a"))]);
+        let config_path = dir.join("config.yaml");
+        let config_path = config_path.to_str().unwrap();
+        let mut reloader = Reloader::new(
+            config_path,
+            parse_config(config_path).unwrap(),
+            SharedRouter::new(Router::new()),
+        );
+
+        dir.write("config.yaml", &config("0.0.0.0:4000", "/b"));
+        reloader.reload().unwrap();
+        assert_eq!(reloader.config.common.listen, "0.0.0.0:3000");
+        assert_eq!(reloader.config.endpoints[0].path, "/b");
+    }
+
+    #[test]
+    fn test_reload_keeps_router_when_routes_are_rejected() {
+        let config = |prefix: &str| {
+            format!("common:\n  graphql_endpoint: http://localhost/\n  path_prefix: {prefix}\nendpoints:\n  - path: /a\n    pq_id: {HASH}\n")
+        };
+        let dir = TestDir::new("reload-routes", &[("config.yaml", config("/api"))]);
+        let config_path = dir.join("config.yaml");
+        let config_path = config_path.to_str().unwrap();
+        let mut reloader = Reloader::new(
+            config_path,
+            parse_config(config_path).unwrap(),
+            SharedRouter::new(Router::new()),
+        );
+
+        // A prefix without a leading `/` passes validation, but the router refuses to nest under it
+        dir.write("config.yaml", &config("api"));
+        match reloader.reload() {
+            Err(ConfigError::Routes { message, .. }) => {
+                assert_eq!(message, "assertion failed: prefix.starts_with('/')")
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("the reload should have been rejected"),
+        }
+        assert_eq!(reloader.config.common.path_prefix, "/api");
+
+        dir.write("config.yaml", &config("/v2"));
+        assert!(reloader.reload().is_ok());
+    }
+}
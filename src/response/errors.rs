@@ -0,0 +1,222 @@
+use axum::http::StatusCode;
+use serde_json::{Map, Value};
+
+use super::ClientResponseError;
+use crate::config::{Endpoint, ErrorFormat, ErrorRedaction, ServerConfig, StatusMapping};
+
+#[derive(Debug, Clone, Default)]
+/// How GraphQL errors are reported for an endpoint, combining the endpoint's settings with the common ones
+pub struct ErrorHandling {
+    /// The endpoint's status mapping rules followed by the common ones, in order of precedence
+    pub status_mapping: Vec<StatusMapping>,
+    /// The endpoint's redaction policy, or the common one if it doesn't have its own
+    pub redaction: Option<ErrorRedaction>,
+    /// The format of error responses
+    pub format: ErrorFormat,
+}
+
+impl ErrorHandling {
+    pub fn new(common: &ServerConfig, endpoint: &Endpoint) -> Self {
+        ErrorHandling {
+            status_mapping: endpoint
+                .status_mapping
+                .iter()
+                .chain(common.status_mapping.iter())
+                .flatten()
+                .cloned()
+                .collect(),
+            redaction: endpoint
+                .redact_errors
+                .clone()
+                .or_else(|| common.redact_errors.clone()),
+            format: endpoint.error_format.unwrap_or(common.error_format),
+        }
+    }
+
+    /// Removes the parts of the errors that the redaction policy doesn't allow to be returned
+    pub fn redact(&self, errors: &mut [ClientResponseError]) {
+        let Some(redaction) = &self.redaction else {
+            return;
+        };
+        for error in errors {
+            if redaction.locations {
+                error.locations = None;
+            }
+            if redaction.path {
+                error.path = None;
+            }
+            if let Some(extensions) = &mut error.extensions {
+                for path in &redaction.extensions {
+                    remove_path(extensions, path);
+                }
+            }
+        }
+    }
+
+    /// Picks the status for a response with errors.
+    /// Each error gets the status of the first rule it matches, or otherwise `206` if there is data and `500` if not, and the highest status wins
+    pub fn status(&self, errors: &[ClientResponseError], has_data: bool) -> StatusCode {
+        let default = if has_data {
+            StatusCode::PARTIAL_CONTENT
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        errors
+            .iter()
+            .map(|error| {
+                self.status_mapping
+                    .iter()
+                    .find(|rule| rule_matches(rule, error))
+                    .and_then(|rule| StatusCode::from_u16(rule.status).ok())
+                    .unwrap_or(default)
+            })
+            .max()
+            .unwrap_or(default)
+    }
+}
+
+fn rule_matches(rule: &StatusMapping, error: &ClientResponseError) -> bool {
+    let code_matches = match &rule.code {
+        Some(code) => {
+            error
+                .extensions
+                .as_ref()
+                .and_then(|e| e.get("code"))
+                .and_then(Value::as_str)
+                == Some(code.as_str())
+        }
+        None => true,
+    };
+    let path_matches = match &rule.path {
+        Some(path) => error
+            .path
+            .as_ref()
+            .is_some_and(|error_path| path_matches(path, error_path)),
+        None => true,
+    };
+    code_matches && path_matches
+}
+
+/// Whether a dotted path such as `user.posts.*.author` is a prefix of the error's path; `*` matches any segment
+fn path_matches(pattern: &str, error_path: &[Value]) -> bool {
+    let segments: Vec<&str> = pattern.split('.').collect();
+    segments.len() <= error_path.len()
+        && segments
+            .iter()
+            .zip(error_path)
+            .all(|(segment, value)| match value {
+                _ if *segment == "*" => true,
+                Value::String(s) => s == segment,
+                Value::Number(n) => n.to_string() == *segment,
+                _ => false,
+            })
+}
+
+/// Removes the value at a dotted path such as `exception.stacktrace`
+fn remove_path(object: &mut Map<String, Value>, path: &str) {
+    match path.split_once('.') {
+        Some((key, rest)) => {
+            if let Some(Value::Object(inner)) = object.get_mut(key) {
+                remove_path(inner, rest);
+            }
+        }
+        None => {
+            object.remove(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(code: Option<&str>, path: Option<&str>, status: u16) -> StatusMapping {
+        StatusMapping {
+            code: code.map(str::to_string),
+            path: path.map(str::to_string),
+            status,
+        }
+    }
+
+    fn error(error: Value) -> ClientResponseError {
+        serde_json::from_value(error).unwrap()
+    }
+
+    #[test]
+    fn test_status() {
+        let handling = ErrorHandling {
+            status_mapping: vec![
+                rule(Some("NOT_FOUND"), Some("user.posts.*"), 410),
+                rule(Some("NOT_FOUND"), None, 404),
+                rule(Some("UNAUTHENTICATED"), None, 401),
+                rule(Some("FORBIDDEN"), None, 403),
+            ],
+            redaction: None,
+            format: ErrorFormat::Graphql,
+        };
+        let not_found =
+            error(json!({"message": "a", "path": ["user"], "extensions": {"code": "NOT_FOUND"}}));
+        let gone = error(
+            json!({"message": "b", "path": ["user", "posts", 1], "extensions": {"code": "NOT_FOUND"}}),
+        );
+        let forbidden = error(json!({"message": "c", "extensions": {"code": "FORBIDDEN"}}));
+        let unmapped = error(json!({"message": "d", "extensions": {"code": "OTHER"}}));
+
+        assert_eq!(
+            handling.status(std::slice::from_ref(&not_found), false),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(handling.status(&[gone], true), StatusCode::GONE);
+        assert_eq!(
+            handling.status(&[not_found.clone(), forbidden], false),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            handling.status(&[not_found.clone(), unmapped.clone()], false),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            handling.status(&[unmapped], true),
+            StatusCode::PARTIAL_CONTENT
+        );
+        assert_eq!(
+            ErrorHandling::default().status(&[not_found], false),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn test_redact() {
+        let handling = ErrorHandling {
+            status_mapping: vec![],
+            redaction: Some(ErrorRedaction {
+                locations: true,
+                path: false,
+                extensions: vec![
+                    "exception.stacktrace".to_string(),
+                    "missing.key".to_string(),
+                ],
+            }),
+            format: ErrorFormat::Graphql,
+        };
+        let mut errors = vec![error(json!({
+            "message": "boom",
+            "locations": [{"line": 1, "column": 2}],
+            "path": ["user"],
+            "extensions": {"code": "INTERNAL", "exception": {"stacktrace": ["at ..."], "name": "Error"}},
+            "serviceName": "users"
+        }))];
+        handling.redact(&mut errors);
+
+        assert_eq!(
+            serde_json::to_value(&errors).unwrap(),
+            json!([{
+                "message": "boom",
+                "path": ["user"],
+                "extensions": {"code": "INTERNAL", "exception": {"name": "Error"}},
+                "serviceName": "users"
+            }])
+        );
+    }
+}
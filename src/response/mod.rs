@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::config::{ResponseConfig, ResponseErrors};
+
+mod errors;
+mod problem;
+mod transform;
+
+pub use errors::ErrorHandling;
+pub use problem::{problem_response, upstream_invalid_params, InvalidParam};
+pub use transform::apply_transforms;
+pub(crate) use transform::transform_schema;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// A GraphQL response from the router
+pub struct ClientResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<ClientResponseError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientResponseError {
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The locations in the operation document that the error refers to; these are kept as-is, since servers may add their own fields
+    pub locations: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The path to the response field that the error applies to; each segment is a field name or a list index
+    pub path: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Map<String, Value>>,
+    #[serde(flatten)]
+    /// Any other fields of the error, which are passed through as-is
+    pub other: Map<String, Value>,
+}
+
+/// Shapes a GraphQL response into the endpoint's response body, transforming its `data` and then selecting the value to return.
+/// Returns `None` if the endpoint should respond with a 404 because the selected value is null
+pub fn shape_response(config: &ResponseConfig, mut response: Value) -> Option<Value> {
+    if let Some(data) = response.get_mut("data") {
+        apply_transforms(&config.transforms, data);
+    }
+    let errors = response
+        .as_object_mut()
+        .and_then(|r| r.remove("errors"))
+        .filter(|e| e.as_array().is_some_and(|e| !e.is_empty()));
+
+    let selected = match config.select.as_deref() {
+        Some(pointer) => response
+            .pointer_mut(pointer)
+            .map(Value::take)
+            .unwrap_or(Value::Null),
+        None => response,
+    };
+
+    if config.not_found_when_null && errors.is_none() {
+        let value = match config.select {
+            Some(_) => &selected,
+            None => selected.get("data").unwrap_or(&Value::Null),
+        };
+        if value.is_null() {
+            return None;
+        }
+    }
+
+    match (errors, config.errors) {
+        (Some(errors), ResponseErrors::KEEP) => match config.select {
+            Some(_) => Some(json!({ "data": selected, "errors": errors })),
+            None => {
+                let mut selected = selected;
+                selected["errors"] = errors;
+                Some(selected)
+            }
+        },
+        _ => Some(selected),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(
+        select: Option<&str>,
+        not_found_when_null: bool,
+        errors: ResponseErrors,
+    ) -> ResponseConfig {
+        ResponseConfig {
+            select: select.map(str::to_string),
+            not_found_when_null,
+            errors,
+            transforms: vec![],
+        }
+    }
+
+    #[test]
+    fn test_shape_response_select() {
+        let response = json!({"data": {"user": {"id": 1, "posts": [{"id": 2}]}}, "extensions": {}});
+        let select = config(Some("/data/user"), false, ResponseErrors::KEEP);
+        assert_eq!(
+            shape_response(&select, response.clone()),
+            Some(json!({"id": 1, "posts": [{"id": 2}]}))
+        );
+
+        let select = config(Some("/data/user/posts/0"), false, ResponseErrors::KEEP);
+        assert_eq!(
+            shape_response(&select, response.clone()),
+            Some(json!({"id": 2}))
+        );
+
+        let select = config(Some("/data/missing"), false, ResponseErrors::KEEP);
+        assert_eq!(shape_response(&select, response), Some(Value::Null));
+    }
+
+    #[test]
+    fn test_shape_response_errors() {
+        let response = json!({"data": {"user": null}, "errors": [{"message": "boom"}]});
+
+        let keep = config(Some("/data/user"), true, ResponseErrors::KEEP);
+        assert_eq!(
+            shape_response(&keep, response.clone()),
+            Some(json!({"data": null, "errors": [{"message": "boom"}]}))
+        );
+
+        let drop = config(Some("/data/user"), false, ResponseErrors::DROP);
+        assert_eq!(shape_response(&drop, response.clone()), Some(Value::Null));
+
+        let not_found = config(Some("/data/user"), true, ResponseErrors::DROP);
+        assert_eq!(
+            shape_response(&not_found, json!({"data": {"user": null}})),
+            None
+        );
+        // A null caused by an error is reported as an error rather than a 404
+        assert_eq!(shape_response(&not_found, response), Some(Value::Null));
+
+        let whole = config(None, true, ResponseErrors::DROP);
+        assert_eq!(shape_response(&whole, json!({"data": null})), None);
+        assert_eq!(
+            shape_response(&whole, json!({"data": {"user": null}, "errors": []})),
+            Some(json!({"data": {"user": null}}))
+        );
+    }
+    #[test]
+    fn test_error_passthrough() {
+        let error = json!({
+            "message": "boom",
+            "locations": [{"line": 1, "column": 2, "source": "users"}, {"line": 3}],
+            "serviceName": "users"
+        });
+        let decoded: ClientResponseError = serde_json::from_value(error.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), error);
+    }
+}
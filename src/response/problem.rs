@@ -0,0 +1,156 @@
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::Json;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::ClientResponseError;
+use crate::config::{Endpoint, ParamKind};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+/// A request parameter that was missing or invalid, for the `invalid_params` of a problem response
+pub struct InvalidParam {
+    /// The name of the parameter in the request
+    pub name: String,
+    #[serde(rename = "in")]
+    /// Where the parameter was expected; one of `path`, `query` or `body`
+    pub location: String,
+    /// The kind of value the parameter expects
+    pub kind: ParamKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The value in the request, if it was present
+    pub received: Option<String>,
+    /// Why the parameter was rejected
+    pub reason: String,
+}
+
+/// Builds an RFC 9457 `application/problem+json` response.
+/// The GraphQL errors are included as an `errors` extension member when the problem came from the GraphQL endpoint
+pub fn problem_response(
+    status: StatusCode,
+    detail: String,
+    instance: &str,
+    invalid_params: &[InvalidParam],
+    errors: Option<&[ClientResponseError]>,
+    mut headers: HeaderMap,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    let mut problem = json!({
+        "type": "about:blank",
+        "title": status.canonical_reason().unwrap_or_default(),
+        "status": status.as_u16(),
+        "detail": detail,
+        "instance": instance,
+    });
+    if !invalid_params.is_empty() {
+        problem["invalid_params"] = json!(invalid_params);
+    }
+    if let Some(errors) = errors {
+        problem["errors"] = json!(errors);
+    }
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/problem+json"),
+    );
+    (status, headers, Json(problem))
+}
+
+/// Finds the parameters that the GraphQL errors refer to.
+/// An error refers to a variable if its `extensions.name` is the variable's name, as in the router's variable validation errors, or if its message mentions `$name`
+pub fn upstream_invalid_params(
+    endpoint: &Endpoint,
+    errors: &[ClientResponseError],
+) -> Vec<InvalidParam> {
+    let sources = [
+        ("path", &endpoint.path_arguments),
+        ("query", &endpoint.query_params),
+        ("body", &endpoint.body_params),
+    ];
+    let mut invalid_params = Vec::new();
+    for (location, params) in sources {
+        for param in params.iter().flatten() {
+            let variable = param.variable();
+            for error in errors.iter().filter(|e| refers_to_variable(e, &variable)) {
+                invalid_params.push(InvalidParam {
+                    name: param.from.clone(),
+                    location: location.to_string(),
+                    kind: param.kind,
+                    received: None,
+                    reason: error.message.clone(),
+                });
+            }
+        }
+    }
+    invalid_params
+}
+
+fn refers_to_variable(error: &ClientResponseError, variable: &str) -> bool {
+    let named = error
+        .extensions
+        .as_ref()
+        .and_then(|e| e.get("name"))
+        .and_then(Value::as_str)
+        == Some(variable);
+    let mentioned =
+        error
+            .message
+            .match_indices(&format!("${}", variable))
+            .any(|(index, matched)| {
+                // Make sure `$id` doesn't match `$idList`
+                !error.message[index + matched.len()..]
+                    .starts_with(|c: char| c.is_alphanumeric() || c == '_')
+            });
+    named || mentioned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Parameter;
+
+    #[test]
+    fn test_upstream_invalid_params() {
+        let endpoint = Endpoint {
+            path: "/users/:id".to_string(),
+            path_arguments: Some(vec![Parameter {
+                from: "id".to_string(),
+                to: Some("userId".to_string()),
+                required: true,
+                kind: ParamKind::STRING,
+                ..Default::default()
+            }]),
+            query_params: Some(vec![Parameter {
+                from: "limit".to_string(),
+                to: None,
+                required: false,
+                kind: ParamKind::STRING,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let errors: Vec<ClientResponseError> = serde_json::from_value(json!([
+            {"message": "Variable \"$userId\" got invalid value \"abc\"", "extensions": {"code": "BAD_USER_INPUT"}},
+            {"message": "invalid type for variable: 'limit'", "extensions": {"name": "limit"}},
+            {"message": "Variable \"$limitTotal\" is not used"},
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            upstream_invalid_params(&endpoint, &errors),
+            vec![
+                InvalidParam {
+                    name: "id".to_string(),
+                    location: "path".to_string(),
+                    kind: ParamKind::STRING,
+                    received: None,
+                    reason: "Variable \"$userId\" got invalid value \"abc\"".to_string(),
+                },
+                InvalidParam {
+                    name: "limit".to_string(),
+                    location: "query".to_string(),
+                    kind: ParamKind::STRING,
+                    received: None,
+                    reason: "invalid type for variable: 'limit'".to_string(),
+                },
+            ]
+        );
+    }
+}
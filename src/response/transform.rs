@@ -0,0 +1,349 @@
+use serde_json::{json, Map, Value};
+
+use crate::config::{FieldCase, Transform};
+
+/// Applies each transform in order to the `data` of a GraphQL response
+pub fn apply_transforms(transforms: &[Transform], data: &mut Value) {
+    for transform in transforms {
+        match transform {
+            Transform::RenameCase { case } => rename_case(data, *case),
+            Transform::DropFields { fields } => drop_fields(data, fields),
+            Transform::FlattenConnections => flatten_connections(data),
+            Transform::AddFields { at, fields } => {
+                if let Some(Value::Object(object)) = data.pointer_mut(at) {
+                    object.extend(fields.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Applies each transform in order to the JSON schema of the `data`, so generated documentation matches the transformed responses
+pub(crate) fn transform_schema(transforms: &[Transform], schema: &mut Value) {
+    for transform in transforms {
+        match transform {
+            Transform::RenameCase { case } => {
+                map_object_schemas(schema, &mut |properties, required| {
+                    *properties = std::mem::take(properties)
+                        .into_iter()
+                        .map(|(key, value)| (convert_case(&key, *case), value))
+                        .collect();
+                    for name in required.iter_mut() {
+                        if let Some(key) = name.as_str() {
+                            *name = json!(convert_case(key, *case));
+                        }
+                    }
+                })
+            }
+            Transform::DropFields { fields } => {
+                map_object_schemas(schema, &mut |properties, required| {
+                    properties.retain(|key, _| !fields.contains(key));
+                    required.retain(|name| {
+                        !name.as_str().is_some_and(|n| fields.iter().any(|f| f == n))
+                    });
+                })
+            }
+            Transform::FlattenConnections => flatten_connection_schemas(schema),
+            Transform::AddFields { at, fields } => {
+                if let Some(target) = schema_pointer_mut(schema, at) {
+                    for (key, value) in fields {
+                        target["properties"][key] = json!({ "const": value });
+                        match target["required"].as_array_mut() {
+                            Some(required) => required.push(json!(key)),
+                            None => target["required"] = json!([key]),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn rename_case(value: &mut Value, case: FieldCase) {
+    match value {
+        Value::Object(object) => {
+            *object = std::mem::take(object)
+                .into_iter()
+                .map(|(key, mut value)| {
+                    rename_case(&mut value, case);
+                    (convert_case(&key, case), value)
+                })
+                .collect();
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| rename_case(item, case)),
+        _ => {}
+    }
+}
+
+fn drop_fields(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Object(object) => {
+            object.retain(|key, _| !fields.contains(key));
+            object.values_mut().for_each(|v| drop_fields(v, fields));
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| drop_fields(item, fields)),
+        _ => {}
+    }
+}
+
+fn flatten_connections(value: &mut Value) {
+    if let Some(nodes) = connection_nodes(value) {
+        *value = Value::Array(nodes);
+    }
+    match value {
+        Value::Object(object) => object.values_mut().for_each(flatten_connections),
+        Value::Array(items) => items.iter_mut().for_each(flatten_connections),
+        _ => {}
+    }
+}
+
+/// Returns the nodes of a connection, or `None` if the value isn't a connection
+fn connection_nodes(value: &Value) -> Option<Vec<Value>> {
+    value
+        .get("edges")?
+        .as_array()?
+        .iter()
+        .map(|edge| edge.get("node").cloned())
+        .collect()
+}
+
+/// Converts a field name between `camelCase` and `snake_case`, keeping any leading underscores such as in `__typename`
+fn convert_case(name: &str, case: FieldCase) -> String {
+    let trimmed = name.trim_start_matches('_');
+    let mut converted = name[..name.len() - trimmed.len()].to_string();
+    let chars: Vec<char> = trimmed.chars().collect();
+    match case {
+        FieldCase::SnakeCase => {
+            for (i, c) in chars.iter().enumerate() {
+                if c.is_uppercase() && i > 0 {
+                    let previous = chars[i - 1];
+                    let next_lowercase = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+                    // Split `userId` as `user_id` and `HTTPStatus` as `http_status`
+                    if previous.is_lowercase()
+                        || previous.is_ascii_digit()
+                        || (previous.is_uppercase() && next_lowercase)
+                    {
+                        converted.push('_');
+                    }
+                }
+                converted.extend(c.to_lowercase());
+            }
+        }
+        FieldCase::CamelCase => {
+            let mut upper = false;
+            for c in chars {
+                if c == '_' {
+                    upper = true;
+                } else if upper {
+                    converted.extend(c.to_uppercase());
+                    upper = false;
+                } else {
+                    converted.push(c);
+                }
+            }
+        }
+    }
+    converted
+}
+
+/// Calls `f` with the properties and required list of every object schema, innermost first
+fn map_object_schemas(
+    schema: &mut Value,
+    f: &mut impl FnMut(&mut Map<String, Value>, &mut Vec<Value>),
+) {
+    if let Some(items) = schema.get_mut("items") {
+        map_object_schemas(items, f);
+    }
+    if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+        properties
+            .values_mut()
+            .for_each(|p| map_object_schemas(p, f));
+    }
+    if let Some(object) = schema.as_object_mut() {
+        let mut properties = match object.remove("properties") {
+            Some(Value::Object(properties)) => properties,
+            _ => return,
+        };
+        let mut required = match object.remove("required") {
+            Some(Value::Array(required)) => required,
+            _ => Vec::new(),
+        };
+        f(&mut properties, &mut required);
+        object.insert("properties".to_string(), Value::Object(properties));
+        if !required.is_empty() {
+            object.insert("required".to_string(), Value::Array(required));
+        }
+    }
+}
+
+fn flatten_connection_schemas(schema: &mut Value) {
+    let node = schema
+        .pointer("/properties/edges/items/properties/node")
+        .cloned();
+    if let Some(node) = node {
+        let nullable = schema["type"]
+            .as_array()
+            .is_some_and(|t| t.contains(&json!("null")));
+        *schema = json!({
+            "type": if nullable { json!(["array", "null"]) } else { json!("array") },
+            "items": node,
+        });
+    }
+    if let Some(items) = schema.get_mut("items") {
+        flatten_connection_schemas(items);
+    }
+    if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+        properties.values_mut().for_each(flatten_connection_schemas);
+    }
+}
+
+/// Follows a JSON Pointer through the properties and items of a schema
+fn schema_pointer_mut<'a>(schema: &'a mut Value, pointer: &str) -> Option<&'a mut Value> {
+    let mut current = schema;
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        let has_property = current
+            .get("properties")
+            .and_then(|p| p.get(&token))
+            .is_some();
+        current = if has_property {
+            current.get_mut("properties")?.get_mut(&token)?
+        } else {
+            current.get_mut("items")?
+        };
+    }
+    Some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_case() {
+        assert_eq!(convert_case("userId", FieldCase::SnakeCase), "user_id");
+        assert_eq!(
+            convert_case("HTTPStatus", FieldCase::SnakeCase),
+            "http_status"
+        );
+        assert_eq!(
+            convert_case("address2Line", FieldCase::SnakeCase),
+            "address2_line"
+        );
+        assert_eq!(
+            convert_case("__typename", FieldCase::SnakeCase),
+            "__typename"
+        );
+        assert_eq!(convert_case("user_id", FieldCase::CamelCase), "userId");
+        assert_eq!(
+            convert_case("__type_name", FieldCase::CamelCase),
+            "__typeName"
+        );
+    }
+
+    #[test]
+    fn test_apply_transforms() {
+        let mut data = json!({
+            "__typename": "Query",
+            "user": {
+                "__typename": "User",
+                "firstName": "Ada",
+                "friendsConnection": {
+                    "totalCount": 2,
+                    "edges": [
+                        {"cursor": "a", "node": {"__typename": "User", "firstName": "Grace"}},
+                        {"cursor": "b", "node": {"__typename": "User", "firstName": "Alan"}},
+                    ],
+                },
+            },
+        });
+        let transforms = vec![
+            Transform::DropFields {
+                fields: vec!["__typename".to_string()],
+            },
+            Transform::FlattenConnections,
+            Transform::RenameCase {
+                case: FieldCase::SnakeCase,
+            },
+            Transform::AddFields {
+                at: "/user".to_string(),
+                fields: json!({"kind": "person"}).as_object().unwrap().clone(),
+            },
+        ];
+        apply_transforms(&transforms, &mut data);
+
+        assert_eq!(
+            data,
+            json!({
+                "user": {
+                    "first_name": "Ada",
+                    "friends_connection": [{"first_name": "Grace"}, {"first_name": "Alan"}],
+                    "kind": "person",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_transform_schema() {
+        let mut schema = json!({
+            "type": "object",
+            "required": ["user"],
+            "properties": {
+                "user": {
+                    "type": ["object", "null"],
+                    "required": ["__typename", "friendsConnection"],
+                    "properties": {
+                        "__typename": {"type": "string"},
+                        "friendsConnection": {
+                            "type": "object",
+                            "required": ["edges"],
+                            "properties": {
+                                "edges": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["node"],
+                                        "properties": {"node": {"type": "string"}},
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        });
+        let transforms = vec![
+            Transform::DropFields {
+                fields: vec!["__typename".to_string()],
+            },
+            Transform::FlattenConnections,
+            Transform::RenameCase {
+                case: FieldCase::SnakeCase,
+            },
+            Transform::AddFields {
+                at: "/user".to_string(),
+                fields: json!({"kind": "person"}).as_object().unwrap().clone(),
+            },
+        ];
+        transform_schema(&transforms, &mut schema);
+
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "required": ["user"],
+                "properties": {
+                    "user": {
+                        "type": ["object", "null"],
+                        "required": ["friends_connection", "kind"],
+                        "properties": {
+                            "friends_connection": {"type": "array", "items": {"type": "string"}},
+                            "kind": {"const": "person"},
+                        },
+                    },
+                },
+            })
+        );
+    }
+}